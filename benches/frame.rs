@@ -0,0 +1,151 @@
+//! How long a frame of the game takes with many items on belts, and how many
+//! items fit in a frame at 60 FPS.
+//!
+//! Each frame runs a tick, moving items along loops of belts and through a
+//! merger and a sorter in each loop, and then places a sprite for every item,
+//! as the game does when they are all on screen. Nothing is rendered, so
+//! what it takes to draw the sprites isn't counted.
+//!
+//! After the criterion benchmarks, `cargo bench` prints the most items whose
+//! frames take less than 16.6 ms on average.
+
+use bevy::prelude::*;
+use criterion::{criterion_group, Criterion, Throughput};
+use multifactory::{
+    direction::Side,
+    headless,
+    items::Item,
+    tilemap::{Layout, MachineType, PlacedMachine, Tilemap},
+};
+use std::time::{Duration, Instant};
+
+/// Length of each loop of belts, in tiles
+const LOOP_LENGTH: i32 = 1000;
+/// How long a frame can take at 60 FPS
+const FRAME_BUDGET: Duration = Duration::from_micros(16_667);
+/// Frames run before timing, so items have spread out through the machines
+const WARM_UP_FRAMES: u32 = 30;
+const TIMED_FRAMES: u32 = 60;
+
+/// `loops` loops of belts, each two tiles high, with a merger and a sorter
+/// along the bottom of each. The sorter's filter matches none of the items,
+/// so every item goes round and round
+fn layout(loops: i32) -> Layout {
+    let width = LOOP_LENGTH / 2;
+    let mut machines = Vec::new();
+    let mut place = |x, y, machine, facing, filter| {
+        machines.push(PlacedMachine {
+            pos: IVec2::new(x, y),
+            machine,
+            facing,
+            filter,
+        });
+    };
+    for y in (0..loops).map(|i| i * 2) {
+        for x in 0..width {
+            let (bottom, filter) = if x == width / 3 {
+                (MachineType::Merger, None)
+            } else if x == width * 2 / 3 {
+                (MachineType::Sorter, Some(Item::B))
+            } else {
+                (MachineType::Belt, None)
+            };
+            let bottom_side = if x == width - 1 {
+                Side::North
+            } else {
+                Side::East
+            };
+            let top_side = if x == 0 { Side::South } else { Side::West };
+            place(x, y, bottom, bottom_side, filter);
+            place(x, y + 1, MachineType::Belt, top_side, None);
+        }
+    }
+    Layout { machines }
+}
+
+/// An app running `loops` loops, with an item on every belt so there is
+/// room for them all to move, and how many items there are
+fn frame_app(loops: i32) -> (App, usize) {
+    let layout = layout(loops);
+    let max = IVec2::new(LOOP_LENGTH / 2, loops * 2).as_vec2();
+    let mut app = headless::frame_app(layout.clone(), -Vec2::ONE, max);
+    let mut tilemap = app.world.resource_mut::<Tilemap>();
+    let lanes = tilemap.lanes_mut();
+    for placed in layout.machines {
+        if placed.machine == MachineType::Belt {
+            lanes
+                .try_insert(placed.pos, placed.facing, Item::A)
+                .unwrap();
+        }
+    }
+    let items = lanes.item_count();
+    (app, items)
+}
+
+fn frame(c: &mut Criterion) {
+    let mut group = c.benchmark_group("frame");
+    group.sample_size(20);
+    for loops in [1, 10, 100] {
+        let (mut app, items) = frame_app(loops);
+        group.throughput(Throughput::Elements(items as u64));
+        group.bench_function(format!("{items} items"), |b| b.iter(|| app.update()));
+    }
+    group.finish();
+}
+
+/// How long a frame takes on average with `loops` loops, and how many items
+/// they have
+fn mean_frame(loops: i32) -> (Duration, usize) {
+    let (mut app, items) = frame_app(loops);
+    for _ in 0..WARM_UP_FRAMES {
+        app.update();
+    }
+    let start = Instant::now();
+    for _ in 0..TIMED_FRAMES {
+        app.update();
+    }
+    (start.elapsed() / TIMED_FRAMES, items)
+}
+
+/// Finds the most loops whose frames fit in [`FRAME_BUDGET`], doubling the
+/// loops until they don't fit and then narrowing down in between
+fn sustainable_items() {
+    let fits = |loops| {
+        let (time, items) = mean_frame(loops);
+        println!("{items} items: {time:.2?} a frame");
+        (time < FRAME_BUDGET).then_some(items)
+    };
+    let mut best = None;
+    let mut loops = 1;
+    while let Some(items) = fits(loops) {
+        best = Some((loops, items));
+        loops *= 2;
+    }
+    let (mut fitting, mut too_many) = (best.map_or(0, |(loops, _)| loops), loops);
+    while too_many - fitting > 1 {
+        let middle = (fitting + too_many) / 2;
+        match fits(middle) {
+            Some(items) => {
+                fitting = middle;
+                best = Some((middle, items));
+            }
+            None => too_many = middle,
+        }
+    }
+    match best {
+        Some((_, items)) => println!("{items} items fit in a frame at 60 FPS"),
+        None => println!("even {LOOP_LENGTH} items don't fit in a frame at 60 FPS"),
+    }
+}
+
+criterion_group!(benches, frame);
+
+fn main() {
+    benches();
+    Criterion::default().configure_from_args().final_summary();
+    // Only when benchmarking, as `cargo test --benches` runs each benchmark
+    // once just to check it works
+    if std::env::args().any(|arg| arg == "--bench") {
+        sustainable_items();
+    }
+}
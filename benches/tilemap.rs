@@ -0,0 +1,139 @@
+//! Compares the chunked tilemap storage with the single `HashMap` it replaced
+
+use bevy::{math::IVec2, utils::HashMap};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use multifactory::tilemap::ChunkMap;
+
+/// Width and height of the square factory each benchmark fills
+const SIZE: i32 = 128;
+
+/// Every tile of the factory, in the order a player might place them
+fn positions() -> Vec<IVec2> {
+    (-SIZE / 2..SIZE / 2)
+        .flat_map(|y| (-SIZE / 2..SIZE / 2).map(move |x| IVec2::new(x, y)))
+        .collect()
+}
+
+fn filled_hash_map(positions: &[IVec2]) -> HashMap<IVec2, u32> {
+    positions.iter().map(|&pos| (pos, pos.x as u32)).collect()
+}
+
+fn filled_chunk_map(positions: &[IVec2]) -> ChunkMap<u32> {
+    let mut map = ChunkMap::default();
+    for &pos in positions {
+        map.insert(pos, pos.x as u32);
+    }
+    map
+}
+
+fn insert(c: &mut Criterion) {
+    let positions = positions();
+    let mut group = c.benchmark_group("insert");
+    group.bench_function("HashMap", |b| {
+        b.iter(|| {
+            let mut map = HashMap::default();
+            for &pos in positions.iter() {
+                map.insert(pos, pos.x as u32);
+            }
+            map
+        })
+    });
+    group.bench_function("ChunkMap", |b| b.iter(|| filled_chunk_map(&positions)));
+    group.finish();
+}
+
+fn lookup(c: &mut Criterion) {
+    let positions = positions();
+    let hash_map = filled_hash_map(&positions);
+    let chunk_map = filled_chunk_map(&positions);
+    // Half the lookups miss, like checking neighbours at the edge of a factory
+    let lookups: Vec<_> = positions.iter().map(|&pos| pos * 2).collect();
+    let mut group = c.benchmark_group("lookup");
+    group.bench_function("HashMap", |b| {
+        b.iter(|| {
+            lookups
+                .iter()
+                .filter_map(|pos| hash_map.get(black_box(pos)))
+                .count()
+        })
+    });
+    group.bench_function("ChunkMap", |b| {
+        b.iter(|| {
+            lookups
+                .iter()
+                .filter_map(|&pos| chunk_map.get(black_box(pos)))
+                .count()
+        })
+    });
+    group.finish();
+}
+
+fn remove(c: &mut Criterion) {
+    let positions = positions();
+    let mut group = c.benchmark_group("remove");
+    group.bench_function("HashMap", |b| {
+        b.iter_batched(
+            || filled_hash_map(&positions),
+            |mut map| {
+                for pos in positions.iter() {
+                    map.remove(pos);
+                    // What `Tilemap::remove` used to do after every removal
+                    if map.capacity() > 16.max(map.len()) {
+                        map.shrink_to(16.max(map.len() + 8));
+                    }
+                }
+                map
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("ChunkMap", |b| {
+        b.iter_batched(
+            || filled_chunk_map(&positions),
+            |mut map| {
+                for &pos in positions.iter() {
+                    map.remove(pos);
+                }
+                map
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+fn iterate(c: &mut Criterion) {
+    let positions = positions();
+    let hash_map = filled_hash_map(&positions);
+    let chunk_map = filled_chunk_map(&positions);
+    let mut group = c.benchmark_group("iterate");
+    group.bench_function("HashMap", |b| {
+        b.iter(|| hash_map.values().copied().map(u64::from).sum::<u64>())
+    });
+    group.bench_function("ChunkMap", |b| {
+        b.iter(|| chunk_map.iter().map(|(_, &v)| u64::from(v)).sum::<u64>())
+    });
+    // A screen's worth of tiles, which is what culling and drawing look at
+    let (min, max) = (IVec2::splat(-20), IVec2::splat(20));
+    group.bench_function("HashMap on screen", |b| {
+        b.iter(|| {
+            hash_map
+                .iter()
+                .filter(|(pos, _)| pos.cmpge(min).all() && pos.cmple(max).all())
+                .count()
+        })
+    });
+    group.bench_function("ChunkMap on screen", |b| {
+        b.iter(|| {
+            chunk_map
+                .chunks_in(min, max)
+                .flat_map(|chunk| chunk.iter())
+                .filter(|(pos, _)| pos.cmpge(min).all() && pos.cmple(max).all())
+                .count()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, insert, lookup, remove, iterate);
+criterion_main!(benches);
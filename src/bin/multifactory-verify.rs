@@ -0,0 +1,46 @@
+//! Checks that a layout solves a level without opening a window.
+//!
+//! Usage: `multifactory-verify <level.ron> <layout.ron> [max ticks]`
+//!
+//! Prints the result as JSON, and exits with 0 if the layout completed the
+//! level, 1 if it didn't and 2 if it couldn't be played. Layouts of
+//! completed levels are saved in the game's data directory under
+//! `solutions`, and `assets/solutions` has a solution to each built in level.
+
+use multifactory::headless::verify;
+use std::{env, fs, process::ExitCode};
+
+/// Ten minutes of game time
+const DEFAULT_MAX_TICKS: u64 = 60 * 60 * 10;
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(e) => {
+            println!("{}", serde_json::json!({ "error": e }));
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Returns whether the level was completed
+fn run() -> Result<bool, String> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (level_path, layout_path, max_ticks) = match args.as_slice() {
+        [level, layout] => (level, layout, DEFAULT_MAX_TICKS),
+        [level, layout, max_ticks] => (
+            level,
+            layout,
+            max_ticks
+                .parse()
+                .map_err(|e| format!("invalid max ticks: {e}"))?,
+        ),
+        _ => return Err("usage: multifactory-verify <level.ron> <layout.ron> [max ticks]".into()),
+    };
+    let read = |path: &String| fs::read_to_string(path).map_err(|e| format!("{path}: {e}"));
+    let verification =
+        verify(&read(level_path)?, &read(layout_path)?, max_ticks).map_err(|e| e.to_string())?;
+    println!("{}", serde_json::to_string(&verification).unwrap());
+    Ok(verification.completed)
+}
@@ -0,0 +1,333 @@
+use bevy::{ecs::system::SystemParam, prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt, fs, hash::Hash, marker::PhantomData, path::PathBuf};
+
+/// How many bindings each action can have
+pub const BINDING_SLOTS: usize = 2;
+
+/// How many toolbar slots have a [`Action::SelectTool`] hotkey
+pub const TOOL_HOTKEYS: u8 = 10;
+
+const CONFIG_FILE_NAME: &str = "bindings.ron";
+
+/// Bindings that were once the default for an action but no longer are
+const OLD_DEFAULTS: [(Action, Binding); 5] = [
+    (Action::UseTool, Binding::Mouse(MouseButton::Right)),
+    (Action::SpawnItemA, Binding::Key(KeyCode::Key1)),
+    (Action::SpawnItemB, Binding::Key(KeyCode::Key2)),
+    (Action::SpawnItemC, Binding::Key(KeyCode::Key3)),
+    (Action::SpawnItemD, Binding::Key(KeyCode::Key4)),
+];
+
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Bindings::load());
+    }
+}
+
+/// Something the player can do with a key or mouse button
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Action {
+    UseTool,
+    Remove,
+    RotateLeft,
+    RotateRight,
+    /// Selects the tool in this toolbar slot, starting from 0
+    SelectTool(u8),
+    SpawnItemA,
+    SpawnItemB,
+    SpawnItemC,
+    SpawnItemD,
+    ClearItems,
+    ToggleStats,
+    /// Saves the game being played, which is loaded again on startup
+    Save,
+    /// Only does anything with the `debug_overlay` feature
+    ToggleDebugOverlay,
+    Back,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+}
+
+/// Which keys and mouse buttons trigger each [`Action`]. Deserializing
+/// fills in any actions that are missing, as [`Bindings::from_ron`] does
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "SavedBindings")]
+pub struct Bindings(HashMap<Action, [Option<Binding>; BINDING_SLOTS]>);
+
+/// Bindings as they were saved, which may be missing actions added since
+#[derive(Deserialize)]
+#[serde(rename = "Bindings")]
+struct SavedBindings(BTreeMap<Action, [Option<Binding>; BINDING_SLOTS]>);
+
+impl From<SavedBindings> for Bindings {
+    fn from(saved: SavedBindings) -> Self {
+        Bindings::from_saved(saved.0)
+    }
+}
+
+impl Action {
+    /// Every action, in the order they are shown in settings
+    pub fn all() -> impl Iterator<Item = Action> {
+        use Action::*;
+        [UseTool, Remove, RotateLeft, RotateRight]
+            .into_iter()
+            .chain((0..TOOL_HOTKEYS).map(SelectTool))
+            .chain([
+                SpawnItemA,
+                SpawnItemB,
+                SpawnItemC,
+                SpawnItemD,
+                ClearItems,
+                ToggleStats,
+                Save,
+            ])
+            .chain(cfg!(feature = "debug_overlay").then_some(ToggleDebugOverlay))
+            .chain([Back])
+    }
+
+    fn default_bindings(self) -> [Option<Binding>; BINDING_SLOTS] {
+        use Action::*;
+        use Binding::*;
+        const NUMBER_KEYS: [KeyCode; TOOL_HOTKEYS as usize] = [
+            KeyCode::Key1,
+            KeyCode::Key2,
+            KeyCode::Key3,
+            KeyCode::Key4,
+            KeyCode::Key5,
+            KeyCode::Key6,
+            KeyCode::Key7,
+            KeyCode::Key8,
+            KeyCode::Key9,
+            KeyCode::Key0,
+        ];
+        match self {
+            UseTool => [Some(Mouse(MouseButton::Left)), None],
+            Remove => [Some(Mouse(MouseButton::Right)), None],
+            RotateLeft => [Some(Key(KeyCode::A)), Some(Key(KeyCode::Left))],
+            RotateRight => [Some(Key(KeyCode::D)), Some(Key(KeyCode::Right))],
+            SelectTool(slot) => [Some(Key(NUMBER_KEYS[slot as usize])), None],
+            SpawnItemA => [Some(Key(KeyCode::F1)), None],
+            SpawnItemB => [Some(Key(KeyCode::F2)), None],
+            SpawnItemC => [Some(Key(KeyCode::F3)), None],
+            SpawnItemD => [Some(Key(KeyCode::F4)), None],
+            ClearItems => [Some(Key(KeyCode::LShift)), None],
+            ToggleStats => [Some(Key(KeyCode::Tab)), None],
+            Save => [Some(Key(KeyCode::F5)), None],
+            ToggleDebugOverlay => [Some(Key(KeyCode::Grave)), None],
+            Back => [Some(Key(KeyCode::Escape)), None],
+        }
+    }
+}
+
+/// A short name shown to the player
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Action::*;
+        match self {
+            UseTool => write!(f, "Use tool"),
+            Remove => write!(f, "Remove"),
+            RotateLeft => write!(f, "Rotate left"),
+            RotateRight => write!(f, "Rotate right"),
+            SelectTool(slot) => write!(f, "Tool {}", slot + 1),
+            SpawnItemA => write!(f, "Spawn item A"),
+            SpawnItemB => write!(f, "Spawn item B"),
+            SpawnItemC => write!(f, "Spawn item C"),
+            SpawnItemD => write!(f, "Spawn item D"),
+            ClearItems => write!(f, "Clear items"),
+            ToggleStats => write!(f, "Toggle stats"),
+            Save => write!(f, "Save game"),
+            ToggleDebugOverlay => write!(f, "Toggle debug overlay"),
+            Back => write!(f, "Back"),
+        }
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Binding::Key(key) => write!(f, "{key:?}"),
+            Binding::Mouse(MouseButton::Other(button)) => write!(f, "Mouse {button}"),
+            Binding::Mouse(button) => write!(f, "{button:?} Mouse"),
+        }
+    }
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Bindings(
+            Action::all()
+                .map(|action| (action, action.default_bindings()))
+                .collect(),
+        )
+    }
+}
+
+impl Bindings {
+    /// Returns the bindings for an action
+    #[must_use]
+    pub fn get(&self, action: Action) -> [Option<Binding>; BINDING_SLOTS] {
+        self.0[&action]
+    }
+
+    /// Binds an action, removing the binding from any other action that used it
+    pub fn set(&mut self, action: Action, slot: usize, binding: Option<Binding>) {
+        if binding.is_some() {
+            for bindings in self.0.values_mut() {
+                for b in bindings.iter_mut().filter(|b| **b == binding) {
+                    *b = None;
+                }
+            }
+        }
+        self.0.get_mut(&action).unwrap()[slot] = binding;
+    }
+
+    fn config_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("multifactory").join(CONFIG_FILE_NAME))
+    }
+
+    /// Loads bindings from the config file, falling back to the defaults if
+    /// it is missing or invalid
+    #[must_use]
+    pub fn load() -> Self {
+        let Some(path) = Self::config_path() else {
+            return Bindings::default();
+        };
+        let Ok(source) = fs::read_to_string(&path) else {
+            return Bindings::default();
+        };
+        Bindings::from_ron(&source).unwrap_or_else(|e| {
+            warn!("Ignoring invalid bindings in {}: {e}", path.display());
+            Bindings::default()
+        })
+    }
+
+    /// Parses saved bindings, using the defaults for any actions they don't
+    /// contain
+    pub fn from_ron(source: &str) -> Result<Self, ron::Error> {
+        ron::from_str(source).map(Bindings::from_saved)
+    }
+
+    fn from_saved(mut saved: BTreeMap<Action, [Option<Binding>; BINDING_SLOTS]>) -> Self {
+        let mut bindings = Bindings::default();
+        // An old default that an action added since the file was saved now
+        // uses was never chosen by the player, so it goes back to the default
+        let added: Vec<Binding> = bindings
+            .0
+            .iter()
+            .filter(|(action, _)| !saved.contains_key(action))
+            .flat_map(|(_, action_bindings)| action_bindings.iter().flatten().copied())
+            .collect();
+        let used: Vec<Binding> = saved.values().flatten().flatten().copied().collect();
+        for (action, old) in OLD_DEFAULTS {
+            let Some(action_bindings) = saved.get_mut(&action) else {
+                continue;
+            };
+            for (binding, default) in action_bindings.iter_mut().zip(action.default_bindings()) {
+                if *binding == Some(old) && added.contains(&old) {
+                    *binding = default.filter(|default| !used.contains(default));
+                }
+            }
+        }
+        // Actions added since the file was saved get their defaults, unless
+        // that would bind something twice
+        let used: Vec<Binding> = saved.values().flatten().flatten().copied().collect();
+        for (action, action_bindings) in bindings.0.iter_mut() {
+            if !saved.contains_key(action) {
+                for binding in action_bindings.iter_mut() {
+                    if binding.is_some_and(|b| used.contains(&b)) {
+                        *binding = None;
+                    }
+                }
+            }
+        }
+        bindings.0.extend(saved);
+        bindings
+    }
+
+    /// Saves bindings to the config file, logging any errors
+    pub fn save(&self) {
+        let Some(path) = Self::config_path() else {
+            warn!("No config directory, bindings will not be saved");
+            return;
+        };
+        let sorted: BTreeMap<_, _> = self.0.iter().collect();
+        let result = ron::ser::to_string_pretty(&sorted, default())
+            .map_err(|e| e.to_string())
+            .and_then(|source| {
+                fs::create_dir_all(path.parent().unwrap()).map_err(|e| e.to_string())?;
+                fs::write(&path, source).map_err(|e| e.to_string())
+            });
+        if let Err(e) = result {
+            error!("Failed to save bindings to {}: {e}", path.display());
+        }
+    }
+}
+
+/// Reads the state of [`Action`]s through the player's [`Bindings`]
+#[derive(SystemParam)]
+pub struct ActionInput<'w, 's> {
+    bindings: Res<'w, Bindings>,
+    keys: Res<'w, Input<KeyCode>>,
+    mouse_buttons: Res<'w, Input<MouseButton>>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+
+impl<'w, 's> ActionInput<'w, 's> {
+    /// Returns true if any binding for `action` is being held
+    #[must_use]
+    pub fn pressed(&self, action: Action) -> bool {
+        self.check(action, ButtonCheck::Pressed)
+    }
+
+    /// Returns true if any binding for `action` was pressed this frame
+    #[must_use]
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.check(action, ButtonCheck::JustPressed)
+    }
+
+    /// Returns true if any binding for `action` was released this frame
+    #[must_use]
+    pub fn just_released(&self, action: Action) -> bool {
+        self.check(action, ButtonCheck::JustReleased)
+    }
+
+    fn check(&self, action: Action, check: ButtonCheck) -> bool {
+        self.bindings
+            .get(action)
+            .into_iter()
+            .flatten()
+            .any(|binding| match binding {
+                Binding::Key(key) => check.check(&self.keys, key),
+                Binding::Mouse(button) => check.check(&self.mouse_buttons, button),
+            })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ButtonCheck {
+    Pressed,
+    JustPressed,
+    JustReleased,
+}
+
+impl ButtonCheck {
+    fn check<T: Copy + Eq + Hash + Send + Sync + 'static>(
+        self,
+        input: &Input<T>,
+        button: T,
+    ) -> bool {
+        match self {
+            ButtonCheck::Pressed => input.pressed(button),
+            ButtonCheck::JustPressed => input.just_pressed(button),
+            ButtonCheck::JustReleased => input.just_released(button),
+        }
+    }
+}
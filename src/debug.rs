@@ -0,0 +1,204 @@
+use crate::{
+    bindings::{Action, ActionInput},
+    items::Momentum,
+    prelude::*,
+    tilemap::{camera_view_rect, world_to_grid_pos, Tilemap},
+    ui::{UiFont, TEXT_COLOR},
+    MainCamera,
+};
+use bevy::{math::Vec3Swizzles, prelude::*};
+use bevy_prototype_debug_lines::{DebugLines, DebugLinesPlugin};
+
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(DebugLinesPlugin::default())
+            .init_resource::<Visible>()
+            .add_system_set(SystemSet::on_enter(AppState::Game).with_system(setup_system))
+            .add_system_set(
+                SystemSet::on_update(AppState::Game)
+                    .with_system(toggle_system)
+                    .with_system(grid_system.after(toggle_system))
+                    .with_system(facing_system.after(toggle_system))
+                    .with_system(momentum_system.after(toggle_system))
+                    .with_system(info_system.after(toggle_system)),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::Game).with_system(despawn_all_system::<DebugText>),
+            );
+    }
+}
+
+/// Whether the overlay is shown; kept between games
+#[derive(Debug, Default)]
+struct Visible(bool);
+
+#[derive(Component)]
+struct DebugText;
+
+/// Drawn above everything else in the world
+const Z: f32 = 10.0;
+const GRID_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.2);
+const FACING_COLOR: Color = Color::YELLOW;
+const MOMENTUM_COLOR: Color = Color::CYAN;
+const HITVEC_COLOR: Color = Color::FUCHSIA;
+
+fn setup_system(mut commands: Commands, font: Res<UiFont>, visible: Res<Visible>) {
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Percent(40.0),
+                    top: Val::Px(8.0),
+                    ..default()
+                },
+                display: if visible.0 {
+                    Display::Flex
+                } else {
+                    Display::None
+                },
+                ..default()
+            },
+            ..font.text("", 16.0, TEXT_COLOR)
+        })
+        .insert(DebugText);
+}
+
+fn toggle_system(
+    actions: ActionInput,
+    mut visible: ResMut<Visible>,
+    mut text_query: Query<&mut Style, With<DebugText>>,
+) {
+    if actions.just_pressed(Action::ToggleDebugOverlay) {
+        visible.0 = !visible.0;
+        text_query.single_mut().display = if visible.0 {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+}
+
+/// Draws the edges of every tile on screen
+fn grid_system(
+    visible: Res<Visible>,
+    camera_query: Query<(&OrthographicProjection, &GlobalTransform), With<MainCamera>>,
+    mut lines: ResMut<DebugLines>,
+) {
+    if !visible.0 {
+        return;
+    }
+    let (projection, camera_transform) = camera_query.single();
+    let (min, max) = camera_view_rect(projection, camera_transform);
+    // Tile edges are halfway between tile centers
+    for x in (min.x - 0.5).ceil() as i32..=(max.x - 0.5).floor() as i32 {
+        let x = x as f32 + 0.5;
+        lines.line_colored(
+            Vec3::new(x, min.y, Z),
+            Vec3::new(x, max.y, Z),
+            0.0,
+            GRID_COLOR,
+        );
+    }
+    for y in (min.y - 0.5).ceil() as i32..=(max.y - 0.5).floor() as i32 {
+        let y = y as f32 + 0.5;
+        lines.line_colored(
+            Vec3::new(min.x, y, Z),
+            Vec3::new(max.x, y, Z),
+            0.0,
+            GRID_COLOR,
+        );
+    }
+}
+
+/// Draws an arrow from `start` to `end`
+fn arrow(lines: &mut DebugLines, start: Vec2, end: Vec2, color: Color) {
+    let dir = end - start;
+    if dir.length_squared() < f32::EPSILON {
+        return;
+    }
+    let head = dir.normalize() * 0.15;
+    lines.line_colored(start.extend(Z), end.extend(Z), 0.0, color);
+    for side in [head.perp(), -head.perp()] {
+        lines.line_colored(end.extend(Z), (end - head + side).extend(Z), 0.0, color);
+    }
+}
+
+/// Draws an arrow for the way each tile on screen is facing
+fn facing_system(
+    visible: Res<Visible>,
+    camera_query: Query<(&OrthographicProjection, &GlobalTransform), With<MainCamera>>,
+    tilemap: Res<Tilemap>,
+    mut lines: ResMut<DebugLines>,
+) {
+    if !visible.0 {
+        return;
+    }
+    let (projection, camera_transform) = camera_query.single();
+    let (min, max) = camera_view_rect(projection, camera_transform);
+    let (min, max) = (world_to_grid_pos(min).tile, world_to_grid_pos(max).tile);
+    for (pos, tile) in tilemap.tiles_in(min, max) {
+        if let Some(facing) = tile.facing() {
+            let center = pos.as_vec2();
+            arrow(
+                &mut lines,
+                center,
+                center + facing.to_vec2() * 0.4,
+                FACING_COLOR,
+            );
+        }
+    }
+}
+
+fn momentum_system(
+    visible: Res<Visible>,
+    items_query: Query<(&Transform, &Momentum)>,
+    mut lines: ResMut<DebugLines>,
+) {
+    if !visible.0 {
+        return;
+    }
+    for (transform, momentum) in items_query.iter() {
+        let pos = transform.translation.xy();
+        // Scaled down so an item on a belt doesn't cover the next tile
+        arrow(&mut lines, pos, pos + momentum.0 * 0.25, MOMENTUM_COLOR);
+    }
+}
+
+/// Shows the hitvec under the mouse and how many entities there are
+fn info_system(
+    visible: Res<Visible>,
+    mouse_input: Res<MouseInput>,
+    entities: Query<Entity>,
+    tilemap: Res<Tilemap>,
+    mut text_query: Query<&mut Text, With<DebugText>>,
+    mut lines: ResMut<DebugLines>,
+) {
+    if !visible.0 {
+        return;
+    }
+    let mut text = format!(
+        "Entities: {}\nItems on belts: {}",
+        entities.iter().count(),
+        tilemap.lanes().item_count()
+    );
+    if let Some(pos) = mouse_input.pos {
+        let corner = pos.tile.as_vec2() - Vec2::splat(0.5);
+        lines.line_colored(
+            corner.extend(Z),
+            (corner + pos.hitvec).extend(Z),
+            0.0,
+            HITVEC_COLOR,
+        );
+        text += &format!(
+            "\nTile: ({}, {})\nHitvec: ({:.2}, {:.2})",
+            pos.tile.x, pos.tile.y, pos.hitvec.x, pos.hitvec.y
+        );
+    }
+    let mut text_display = text_query.single_mut();
+    if text_display.sections[0].value != text {
+        text_display.sections[0].value = text;
+    }
+}
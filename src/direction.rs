@@ -1,4 +1,5 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::f32::consts::PI;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -7,7 +8,7 @@ pub enum Axis2d {
     Y,
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Side {
     #[default]
     North,
@@ -107,12 +108,17 @@ impl Side {
     }
 
     rotate_2d_vector!(rotate_vec2, Vec2);
-}
+    rotate_2d_vector!(rotate_ivec2, IVec2);
 
-#[derive(Debug)]
-pub struct SideArr<T> {
-    pub north: T,
-    pub east: T,
-    pub south: T,
-    pub west: T,
+    /// Takes a side oriented `North` and returns one oriented this direction
+    #[must_use]
+    pub fn rotate_side(self, side: Side) -> Side {
+        use Side::*;
+        match self {
+            North => side,
+            East => side.rotate_right(),
+            South => side.opposite(),
+            West => side.rotate_left(),
+        }
+    }
 }
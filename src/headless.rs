@@ -0,0 +1,255 @@
+//! Plays a level without a window, to check that a layout solves it or to
+//! play back a recording of a game
+
+use crate::{
+    bindings::Bindings,
+    items::{self, sprites::ItemImageCache, Item},
+    levels::{Level, Levels, Session},
+    placing::{self, Budget},
+    replay::{self, Playback, Recording},
+    rewind,
+    scores::{self, GoalProgress},
+    simulation::{self, FixedTicks, SimClock},
+    stats::{ItemEvent, ItemEventKind},
+    tilemap::{self, Layout, MachineType, TextureMap, Tilemap},
+    AppState, MainCamera, MouseInput,
+};
+use bevy::{asset::AssetPlugin, prelude::*, render::camera::ScalingMode};
+use serde::Serialize;
+use std::fmt;
+
+pub use crate::scores::Score;
+
+/// What happened when a layout was played
+#[derive(Debug, Clone, Serialize)]
+pub struct Verification {
+    /// Whether the level's goal was met
+    pub completed: bool,
+    /// How many ticks were simulated
+    pub ticks: u64,
+    /// How many goal items were delivered
+    pub delivered: u32,
+    /// The score of the layout, if it completed the level
+    pub score: Option<Score>,
+}
+
+/// Why a layout couldn't be played
+#[derive(Debug)]
+pub enum VerifyError {
+    InvalidLevel(ron::Error),
+    InvalidLayout(ron::Error),
+    /// The level has no goal, so can't be completed
+    NoGoal,
+    /// The layout uses more of a machine than the level's budget allows
+    OverBudget(MachineType),
+    /// A machine overlaps another tile or is outside the build area
+    Unplaceable {
+        pos: IVec2,
+        machine: MachineType,
+    },
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::InvalidLevel(e) => write!(f, "invalid level: {e}"),
+            VerifyError::InvalidLayout(e) => write!(f, "invalid layout: {e}"),
+            VerifyError::NoGoal => write!(f, "the level has no goal"),
+            VerifyError::OverBudget(machine) => {
+                write!(f, "the layout uses too many of {}", machine.name())
+            }
+            VerifyError::Unplaceable { pos, machine } => write!(
+                f,
+                "{} at ({}, {}) can't be placed there",
+                machine.name(),
+                pos.x,
+                pos.y
+            ),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+/// Plays the level in `level_source` with the layout in `layout_source`,
+/// both as RON, for at most `max_ticks` ticks or until the goal is met.
+/// The same level and layout always give the same result
+pub fn verify(
+    level_source: &str,
+    layout_source: &str,
+    max_ticks: u64,
+) -> Result<Verification, VerifyError> {
+    let level: Level = ron::from_str(level_source).map_err(VerifyError::InvalidLevel)?;
+    if level.goal.is_none() {
+        return Err(VerifyError::NoGoal);
+    }
+    let mut app = layout_app(level, layout_source)?;
+    while app.world.resource::<SimClock>().tick() < max_ticks
+        && app.world.resource::<GoalProgress>().score().is_none()
+    {
+        app.update();
+    }
+
+    let progress = app.world.resource::<GoalProgress>();
+    Ok(Verification {
+        completed: progress.score().is_some(),
+        ticks: app.world.resource::<SimClock>().tick(),
+        delivered: progress.delivered(),
+        score: progress.score(),
+    })
+}
+
+/// Plays the level in `level_source` with the layout in `layout_source`,
+/// both as RON, for `ticks` ticks, and returns every item delivered to a
+/// target in the order they arrived
+pub fn deliveries(
+    level_source: &str,
+    layout_source: &str,
+    ticks: u64,
+) -> Result<Vec<Item>, VerifyError> {
+    let level: Level = ron::from_str(level_source).map_err(VerifyError::InvalidLevel)?;
+    let mut app = layout_app(level, layout_source)?;
+    app.init_resource::<Deliveries>()
+        .add_system_to_stage(CoreStage::Last, record_deliveries_system);
+    while app.world.resource::<SimClock>().tick() < ticks {
+        app.update();
+    }
+    Ok(app.world.remove_resource::<Deliveries>().unwrap().0)
+}
+
+/// Items delivered to targets so far, in the order they arrived
+#[derive(Debug, Default)]
+struct Deliveries(Vec<Item>);
+
+fn record_deliveries_system(
+    mut item_events: EventReader<ItemEvent>,
+    mut deliveries: ResMut<Deliveries>,
+) {
+    for event in item_events.iter() {
+        if event.kind == ItemEventKind::Delivered {
+            deliveries.0.push(event.item.clone());
+        }
+    }
+}
+
+/// An app playing `level` with the layout in `layout_source` placed, which
+/// runs a tick each update. Fails if the layout breaks the level's rules
+fn layout_app(level: Level, layout_source: &str) -> Result<App, VerifyError> {
+    let layout: Layout = ron::from_str(layout_source).map_err(VerifyError::InvalidLayout)?;
+    let mut budget = Budget::new(Some(&level));
+    for placed in layout.machines.iter() {
+        if !budget.can_afford(placed.machine) {
+            return Err(VerifyError::OverBudget(placed.machine));
+        }
+        budget.spend(placed.machine);
+    }
+
+    let mut app = game_app(Some(level), layout.clone());
+    app.update();
+
+    let loaded = app.world.resource::<Tilemap>().layout();
+    if let Some(missing) = layout
+        .machines
+        .iter()
+        .find(|placed| !loaded.machines.contains(placed))
+    {
+        return Err(VerifyError::Unplaceable {
+            pos: missing.pos,
+            machine: missing.machine,
+        });
+    }
+
+    app.insert_resource(FixedTicks(1));
+    Ok(app)
+}
+
+/// What a recording left behind once it finished playing
+#[derive(Debug, Clone)]
+pub struct Replayed {
+    /// The machines placed when the recording ended
+    pub layout: Layout,
+    /// How many ticks were simulated
+    pub ticks: u64,
+    /// How many goal items were delivered
+    pub delivered: u32,
+}
+
+/// Plays back `recording` on the level in `level_source` as RON, or in
+/// sandbox mode if `None`. The same recording always gives the same result,
+/// just as it did when it was recorded
+pub fn replay(level_source: Option<&str>, recording: Recording) -> Result<Replayed, ron::Error> {
+    let level = level_source.map(ron::from_str).transpose()?;
+    let mut app = game_app(level, recording.layout.clone());
+    app.init_resource::<MouseInput>()
+        .init_resource::<Input<KeyCode>>()
+        .init_resource::<Input<MouseButton>>()
+        .init_resource::<Bindings>()
+        .insert_resource(Playback::new(recording))
+        .add_plugin(placing::HeadlessPlugin)
+        .add_plugin(rewind::HeadlessPlugin)
+        .add_system_set_to_stage(CoreStage::PreUpdate, replay::playback_systems())
+        .add_system_set(
+            SystemSet::on_enter(AppState::Game).with_system(replay::begin_playback_system),
+        );
+    while !app.world.resource::<Playback>().is_finished() {
+        app.update();
+    }
+
+    Ok(Replayed {
+        layout: app.world.resource::<Tilemap>().layout(),
+        ticks: app.world.resource::<SimClock>().tick(),
+        delivered: app.world.resource::<GoalProgress>().delivered(),
+    })
+}
+
+/// An app that plays `layout` in sandbox mode, which runs a tick each update
+/// and then places a sprite for each item on belts between `min` and `max`,
+/// as a frame of the game does. The layout is already placed.
+///
+/// Nothing is rendered, so this leaves out what it takes to draw the sprites
+pub fn frame_app(layout: Layout, min: Vec2, max: Vec2) -> App {
+    let mut app = game_app(None, layout);
+    app.add_plugin(AssetPlugin)
+        .add_asset::<Image>()
+        .add_asset::<TextureAtlas>()
+        .init_resource::<ItemImageCache>()
+        .add_system_set(
+            SystemSet::on_update(AppState::Game).with_system(items::draw_lane_items_system),
+        );
+    app.world
+        .spawn()
+        .insert(OrthographicProjection {
+            left: min.x,
+            right: max.x,
+            bottom: min.y,
+            top: max.y,
+            scaling_mode: ScalingMode::None,
+            ..default()
+        })
+        .insert(GlobalTransform::default())
+        .insert(MainCamera);
+    app.update();
+    app.insert_resource(FixedTicks(1));
+    app
+}
+
+/// An app that plays `level` with `layout` already placed, or sandbox mode
+/// if there is no level. The level is loaded by the first update, which
+/// runs no ticks
+fn game_app(level: Option<Level>, layout: Layout) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_state(AppState::Game)
+        .insert_resource(FixedTicks(0))
+        .insert_resource(Tilemap::new(TextureMap::default()))
+        .insert_resource(Session {
+            level: level.is_some().then_some(0),
+            layout,
+        })
+        .insert_resource(level.map_or_else(Levels::default, Levels::single))
+        .add_plugin(simulation::Plugin)
+        .add_plugin(tilemap::HeadlessPlugin)
+        .add_plugin(items::HeadlessPlugin)
+        .add_plugin(scores::HeadlessPlugin);
+    app
+}
@@ -1,112 +1,629 @@
-use crate::{prelude::*, tilemap::*};
+use crate::{
+    bindings::{Action, ActionInput},
+    prelude::*,
+    simulation::{SimClock, SimulationStage, TICK_SECONDS},
+    stats::{ItemEvent, ItemEventKind},
+    tilemap::*,
+    MainCamera,
+};
 use bevy::{math::Vec3Swizzles, prelude::*};
+use serde::{Deserialize, Serialize};
+use std::{fmt, sync::Arc};
 
-const BELT_SPEED: f32 = 2.0;
+pub mod sprites;
+
+pub use sprites::ItemImages;
+
+/// How fast items move along belts, in tiles per second
+pub const BELT_SPEED: f32 = 2.0;
+/// How close an item can be to a machine's output before it blocks it
+const OUTPUT_CLEARANCE: f32 = 0.75;
+/// How many ticks a source takes to make each item
+pub const SOURCE_INTERVAL: u64 = 30;
 
 pub struct Plugin;
 
 impl bevy::prelude::Plugin for Plugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(
-            SystemSet::on_update(AppState::Game)
-                .with_system(item_momentum_system)
-                .with_system(temp_spawn_items_system),
-        );
+        app.add_plugin(HeadlessPlugin)
+            .add_plugin(sprites::Plugin)
+            .add_system_set(
+                SystemSet::on_update(AppState::Game)
+                    .with_system(draw_lane_items_system)
+                    .with_system(temp_spawn_items_system),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::Game)
+                    .with_system(despawn_all_system::<Item>)
+                    .with_system(despawn_all_system::<LaneItemSprite>),
+            );
     }
 }
 
-#[derive(Debug, Component)]
+/// Moves and makes items, without drawing them
+pub(crate) struct HeadlessPlugin;
+
+impl bevy::prelude::Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ItemEvent>()
+            .add_system_set_to_stage(SimulationStage, simulation_systems());
+    }
+}
+
+/// Systems that move and make items, which run every tick in
+/// [`SimulationStage`], one after another so ticks are deterministic
+fn simulation_systems() -> SystemSet {
+    SystemSet::new()
+        .with_system(source_system)
+        .with_system(item_momentum_system.after(source_system))
+        .with_system(lane_system.after(item_momentum_system))
+        .with_system(combiner_system.after(lane_system))
+        .with_system(sorter_system.after(combiner_system))
+        .with_system(merger_system.after(sorter_system))
+        .with_system(disassembler_system.after(merger_system))
+}
+
+/// Something that moves along belts and goes into machines. There are a few
+/// base items, and any items can be combined into a new one
+#[derive(Debug, Component, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum Item {
     A,
     B,
     C,
     D,
+    /// Combined from these items, in order
+    Compound(Arc<[Item]>),
 }
 
+/// How fast an item that isn't on a belt is moving, in tiles per second
 #[derive(Debug, Component, Default, Clone, Copy)]
-struct Momentum(Vec2);
+pub struct Momentum(pub Vec2);
+
+/// A sprite showing an item on a belt. These are reused every frame for
+/// whichever items are on screen
+#[derive(Debug, Component)]
+pub(crate) struct LaneItemSprite;
+
+const ITEM_Z: f32 = 6.0;
+const ITEM_SIZE: f32 = 0.5;
+
+/// How many ticks combining or taking apart takes for each base item in
+/// the combined item
+pub const COMBINE_TICKS_PER_ITEM: u32 = 15;
+
+impl Item {
+    /// Combines `components` into one item, which keeps them in order
+    #[must_use]
+    pub fn combine(components: impl IntoIterator<Item = Item>) -> Item {
+        Item::Compound(components.into_iter().collect())
+    }
+
+    /// The items this was combined from, or nothing for a base item
+    #[must_use]
+    pub fn components(&self) -> &[Item] {
+        match self {
+            Item::Compound(components) => components,
+            _ => &[],
+        }
+    }
+
+    /// How many base items went into this
+    #[must_use]
+    pub fn base_count(&self) -> u32 {
+        match self {
+            Item::Compound(components) => components.iter().map(Item::base_count).sum(),
+            _ => 1,
+        }
+    }
+
+    /// How many ticks it takes to combine this from its components, or to
+    /// take it apart into them
+    #[must_use]
+    pub fn combine_ticks(&self) -> u32 {
+        self.base_count() * COMBINE_TICKS_PER_ITEM
+    }
+
+    /// Index of a base item's sprite in the texture atlas, or `None` for a
+    /// combined item, whose image is made from its components' sprites
+    #[must_use]
+    pub fn texture(&self, textures: &TextureMap) -> Option<usize> {
+        match self {
+            Item::A => Some(textures.item_a),
+            Item::B => Some(textures.item_b),
+            Item::C => Some(textures.item_c),
+            Item::D => Some(textures.item_d),
+            Item::Compound(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Item {
+    /// Base items are their letter, and combined items list their
+    /// components, with combined components in brackets, like `(AA)B`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Item::A => write!(f, "A"),
+            Item::B => write!(f, "B"),
+            Item::C => write!(f, "C"),
+            Item::D => write!(f, "D"),
+            Item::Compound(components) => {
+                components.iter().try_for_each(|component| match component {
+                    Item::Compound(_) => write!(f, "({component})"),
+                    _ => write!(f, "{component}"),
+                })
+            }
+        }
+    }
+}
+
+/// Spawns an item that isn't on a belt. Its sprite is added before it is
+/// drawn
+pub fn spawn_item(commands: &mut Commands, item: Item, pos: Vec2, momentum: Vec2) {
+    commands
+        .spawn_bundle(SpatialBundle::from_transform(Transform::from_translation(
+            pos.extend(ITEM_Z),
+        )))
+        .insert(Momentum(momentum))
+        .insert(item);
+}
+
+/// Puts an item on the belt at `pos`, or spawns it if there isn't one or
+/// the belt is full
+fn place_item(commands: &mut Commands, tilemap: &mut Tilemap, item: Item, pos: Vec2) {
+    if let Err(item) = tilemap.lanes_mut().try_insert_at(pos, item) {
+        spawn_item(commands, item, pos, Vec2::ZERO);
+    }
+}
 
 fn temp_spawn_items_system(
     mut commands: Commands,
     mouse_input: Res<MouseInput>,
-    keys: Res<Input<KeyCode>>,
-    tilemap: Res<Tilemap>,
+    actions: ActionInput,
+    mut tilemap: ResMut<Tilemap>,
     items_query: Query<Entity, With<Item>>,
+    mut item_events: EventWriter<ItemEvent>,
 ) {
     if let Some(pos) = mouse_input.pos {
-        let item_data = if keys.just_pressed(KeyCode::Key1) {
-            Some((Item::A, tilemap.textures().item_a.clone()))
-        } else if keys.just_pressed(KeyCode::Key2) {
-            Some((Item::B, tilemap.textures().item_b.clone()))
-        } else if keys.just_pressed(KeyCode::Key3) {
-            Some((Item::C, tilemap.textures().item_c.clone()))
-        } else if keys.just_pressed(KeyCode::Key4) {
-            Some((Item::D, tilemap.textures().item_d.clone()))
+        let item = if actions.just_pressed(Action::SpawnItemA) {
+            Some(Item::A)
+        } else if actions.just_pressed(Action::SpawnItemB) {
+            Some(Item::B)
+        } else if actions.just_pressed(Action::SpawnItemC) {
+            Some(Item::C)
+        } else if actions.just_pressed(Action::SpawnItemD) {
+            Some(Item::D)
         } else {
             None
         };
-        if let Some((item, index)) = item_data {
-            commands
-                .spawn_bundle(SpriteSheetBundle {
-                    sprite: TextureAtlasSprite {
-                        index,
-                        custom_size: Some(Vec2::splat(0.5)),
-                        ..default()
-                    },
-                    texture_atlas: tilemap.atlas().clone(),
-                    transform: transform_from_grid_pos(pos.tile, 6.0, Side::North),
-                    ..default()
-                })
-                .insert(Momentum::default())
-                .insert(item);
+        if let Some(item) = item {
+            place_item(
+                &mut commands,
+                &mut tilemap,
+                item.clone(),
+                pos.tile.as_vec2(),
+            );
+            item_events.send(ItemEvent {
+                kind: ItemEventKind::Produced,
+                item,
+                machine: None,
+            });
         }
     }
 
-    if keys.just_pressed(KeyCode::LShift) {
+    if actions.just_pressed(Action::ClearItems) {
         for item in items_query.iter() {
-            commands.entity(item).despawn();
+            commands.entity(item).despawn_recursive();
         }
+        tilemap.lanes_mut().clear_items();
     }
 }
 
-fn item_momentum_system(
-    mut items_query: Query<(&mut Transform, &mut Momentum), With<Item>>,
-    tilemap: Res<Tilemap>,
-    time: Res<Time>,
+/// Puts items from sources onto the belts next to them
+fn source_system(
+    clock: Res<SimClock>,
+    mut tilemap: ResMut<Tilemap>,
+    mut item_events: EventWriter<ItemEvent>,
+) {
+    if !clock.tick().is_multiple_of(SOURCE_INTERVAL) {
+        return;
+    }
+    let mut sources: Vec<_> = tilemap
+        .tiles()
+        .filter_map(|(pos, tile)| match tile {
+            Tile::Terrain(Terrain::Source(item), _) => Some((pos, item.clone())),
+            _ => None,
+        })
+        .collect();
+    // Sorted so sources feeding the same belt always take turns the same way
+    sources.sort_by_key(|(pos, _)| (pos.y, pos.x));
+    for (pos, item) in sources {
+        for side in [Side::North, Side::East, Side::South, Side::West] {
+            let belt = pos + side.to_ivec2();
+            if tilemap
+                .lanes_mut()
+                .try_insert(belt, side, item.clone())
+                .is_ok()
+            {
+                item_events.send(ItemEvent {
+                    kind: ItemEventKind::Produced,
+                    item: item.clone(),
+                    machine: Some(pos),
+                });
+            }
+        }
+    }
+}
+
+/// Moves items that aren't on belts, and puts them on belts or into
+/// machines when they reach one
+pub(crate) fn item_momentum_system(
+    mut commands: Commands,
+    mut items_query: Query<(Entity, &Item, &mut Transform, &mut Momentum)>,
+    mut tilemap: ResMut<Tilemap>,
+    mut item_events: EventWriter<ItemEvent>,
 ) {
-    for (mut transform, mut momentum) in items_query.iter_mut() {
-        match tilemap.get_tile(world_to_grid_pos(transform.translation.xy()).tile) {
+    for (entity, item, mut transform, mut momentum) in items_query.iter_mut() {
+        let pos = transform.translation.xy();
+        let tile = world_to_grid_pos(pos).tile;
+        match tilemap.get_tile(tile) {
             None => momentum.0 = Vec2::ZERO,
-            Some(Tile::Belt(side, _)) => {
-                macro_rules! update_momentum {
-                    ($main:ident, $cross:ident) => {
-                        momentum.0.$cross = 0.0;
-                        let seperation = pos_fract(transform.translation.$cross + 0.5) - 0.5;
-                        let dist = seperation.abs();
-                        transform.translation.$cross -=
-                            seperation.signum() * dist.min(time.delta_seconds() * BELT_SPEED);
-                        if dist < f32::EPSILON {
-                            momentum.0.$main = side.to_vec2().$main * BELT_SPEED;
-                        }
-                    };
+            Some(Tile::Belt(..)) => match tilemap.lanes_mut().try_insert_at(pos, item.clone()) {
+                Ok(()) => {
+                    commands.entity(entity).despawn_recursive();
+                    continue;
                 }
-                match side.axis() {
-                    Axis2d::X => {
-                        update_momentum!(x, y);
+                // Wait until there is space on the belt
+                Err(_) => momentum.0 = Vec2::ZERO,
+            },
+            Some(Tile::Ice(_) | Tile::Terrain(Terrain::Ice, _)) => (),
+            Some(Tile::Terrain(Terrain::Target, _)) => {
+                commands.entity(entity).despawn_recursive();
+                item_events.send(ItemEvent {
+                    kind: ItemEventKind::Delivered,
+                    item: item.clone(),
+                    machine: Some(tile),
+                });
+                continue;
+            }
+            // Items never move onto these, so can only be here if placed on them
+            Some(Tile::Terrain(..)) => momentum.0 = Vec2::ZERO,
+            Some(Tile::Part(_) | Tile::Combiner(_)) => {
+                let Some((pos, input, combiner)) = tilemap.combiner_mut(tile) else {
+                    momentum.0 = Vec2::ZERO;
+                    continue;
+                };
+                // Items that have stopped go in through the edge they're
+                // nearest, so ones waiting for an input stay at that input
+                let travel = travel_side(momentum.0)
+                    .or_else(|| travel_side(tile.as_vec2() - transform.translation.xy()))
+                    .unwrap_or_else(|| combiner.output_side());
+                match combiner.try_insert(input, travel, item.clone()) {
+                    Ok(()) => {
+                        commands.entity(entity).despawn_recursive();
+                        item_events.send(ItemEvent {
+                            kind: ItemEventKind::Consumed,
+                            item: item.clone(),
+                            machine: Some(pos),
+                        });
+                        continue;
                     }
-                    Axis2d::Y => {
-                        update_momentum!(y, x);
+                    // Wait on the input until there is space
+                    Err(_) => momentum.0 = Vec2::ZERO,
+                }
+            }
+            Some(sorter @ Tile::Sorter(_)) => {
+                // Items that have stopped go in the back
+                let travel = travel_side(momentum.0).or_else(|| sorter.facing()).unwrap();
+                match tilemap
+                    .machine_mut::<Sorter>(tile)
+                    .unwrap()
+                    .try_insert(travel, item.clone())
+                {
+                    Ok(()) => {
+                        commands.entity(entity).despawn_recursive();
+                        continue;
+                    }
+                    Err(_) => momentum.0 = Vec2::ZERO,
+                }
+            }
+            Some(Tile::Merger(m)) => {
+                // Items that have stopped go in the back
+                let travel = travel_side(momentum.0).unwrap_or_else(|| m.output_side());
+                match tilemap
+                    .machine_mut::<Merger>(tile)
+                    .unwrap()
+                    .try_insert(travel, item.clone())
+                {
+                    Ok(()) => {
+                        commands.entity(entity).despawn_recursive();
+                        continue;
+                    }
+                    Err(_) => momentum.0 = Vec2::ZERO,
+                }
+            }
+            Some(Tile::Disassembler(d)) => {
+                // Items that have stopped go in the back
+                let travel = travel_side(momentum.0).unwrap_or(d.output_sides()[2]);
+                match tilemap
+                    .machine_mut::<Disassembler>(tile)
+                    .unwrap()
+                    .try_insert(travel, item.clone())
+                {
+                    Ok(()) => {
+                        commands.entity(entity).despawn_recursive();
+                        item_events.send(ItemEvent {
+                            kind: ItemEventKind::Consumed,
+                            item: item.clone(),
+                            machine: Some(tile),
+                        });
+                        continue;
                     }
+                    Err(_) => momentum.0 = Vec2::ZERO,
                 }
             }
-            Some(Tile::Ice(_)) => (),
-            _ => todo!(),
         }
 
-        transform.translation += (momentum.0 * time.delta_seconds()).extend(0.0);
+        let new_pos = pos + momentum.0 * TICK_SECONDS;
+        if momentum.0 != Vec2::ZERO {
+            // Stop at the edge of walls rather than moving into them
+            let front = new_pos + momentum.0.normalize() * ITEM_SIZE / 2.0;
+            let front_tile = world_to_grid_pos(front).tile;
+            if tilemap.get_tile(front_tile).is_some_and(Tile::blocks_items) {
+                momentum.0 = Vec2::ZERO;
+                continue;
+            }
+        }
+        transform.translation = new_pos.extend(transform.translation.z);
+    }
+}
+
+/// Moves items along belts
+pub(crate) fn lane_system(
+    mut commands: Commands,
+    mut tilemap: ResMut<Tilemap>,
+    mut item_events: EventWriter<ItemEvent>,
+) {
+    for (item, exit) in tilemap.update_lanes(TICK_SECONDS * BELT_SPEED) {
+        match exit {
+            LaneExit::Consumed(machine) => item_events.send(ItemEvent {
+                kind: ItemEventKind::Consumed,
+                item,
+                machine: Some(machine),
+            }),
+            LaneExit::Delivered(target) => item_events.send(ItemEvent {
+                kind: ItemEventKind::Delivered,
+                item,
+                machine: Some(target),
+            }),
+            LaneExit::Dropped { pos, travel } => {
+                let momentum = travel.to_vec2() * BELT_SPEED;
+                spawn_item(&mut commands, item, pos, momentum);
+            }
+        }
+    }
+}
+
+/// Works on combiners with every input full, and outputs
+/// the combined item once it is done, as long as nothing is in the way
+fn combiner_system(
+    mut commands: Commands,
+    items_query: Query<&Transform, With<Item>>,
+    mut tilemap: ResMut<Tilemap>,
+    mut item_events: EventWriter<ItemEvent>,
+) {
+    for (_, combiner) in tilemap.machines_mut::<Combiner>() {
+        combiner.tick();
+    }
+    for (pos, item) in output_machines::<Combiner>(&mut commands, &items_query, &mut tilemap) {
+        item_events.send(ItemEvent {
+            kind: ItemEventKind::Combined,
+            item,
+            machine: Some(pos),
+        });
     }
 }
 
-fn pos_fract(x: f32) -> f32 {
-    (x.fract() + 1.0).fract()
+/// Sends the item in each sorter out of the side its filter picks, as long
+/// as nothing is in the way
+fn sorter_system(
+    mut commands: Commands,
+    items_query: Query<&Transform, With<Item>>,
+    mut tilemap: ResMut<Tilemap>,
+) {
+    output_machines::<Sorter>(&mut commands, &items_query, &mut tilemap);
+}
+
+/// Sends items out of each merger, one input at a time, as long as nothing
+/// is in the way
+fn merger_system(
+    mut commands: Commands,
+    items_query: Query<&Transform, With<Item>>,
+    mut tilemap: ResMut<Tilemap>,
+) {
+    output_machines::<Merger>(&mut commands, &items_query, &mut tilemap);
+}
+
+/// Takes apart the items in disassemblers, and sends out each item taken
+/// apart that nothing is in the way of
+fn disassembler_system(
+    mut commands: Commands,
+    items_query: Query<&Transform, With<Item>>,
+    mut tilemap: ResMut<Tilemap>,
+    mut item_events: EventWriter<ItemEvent>,
+) {
+    for (_, disassembler) in tilemap.machines_mut::<Disassembler>() {
+        disassembler.tick();
+    }
+    for (pos, item) in output_machines::<Disassembler>(&mut commands, &items_query, &mut tilemap) {
+        item_events.send(ItemEvent {
+            kind: ItemEventKind::Disassembled,
+            item,
+            machine: Some(pos),
+        });
+    }
+}
+
+/// The side an item with `momentum` is moving towards, going by whichever
+/// way it is moving fastest, or `None` if it has stopped
+fn travel_side(momentum: Vec2) -> Option<Side> {
+    if momentum == Vec2::ZERO {
+        None
+    } else if momentum.x.abs() > momentum.y.abs() {
+        Some(if momentum.x > 0.0 {
+            Side::East
+        } else {
+            Side::West
+        })
+    } else {
+        Some(if momentum.y > 0.0 {
+            Side::North
+        } else {
+            Side::South
+        })
+    }
+}
+
+/// Sends out the items every machine of type `M` has ready, as long as
+/// nothing is in the way, returning each item that went out and the
+/// position of the machine it left
+fn output_machines<M: Machine>(
+    commands: &mut Commands,
+    items_query: &Query<&Transform, With<Item>>,
+    tilemap: &mut Tilemap,
+) -> Vec<(IVec2, Item)> {
+    let ready: Vec<_> = tilemap
+        .machines::<M>()
+        .map(|(pos, machine)| (pos, machine.ready_outputs()))
+        .filter(|(_, outputs)| !outputs.is_empty())
+        .collect();
+    let mut sent = Vec::new();
+    for (pos, outputs) in ready {
+        let output: Vec<_> = outputs
+            .into_iter()
+            .map(|(side, item)| {
+                let is_output =
+                    output_item(commands, items_query, tilemap, pos, side, item.clone());
+                if is_output {
+                    sent.push((pos, item));
+                }
+                is_output
+            })
+            .collect();
+        tilemap
+            .machine_mut::<M>(pos)
+            .unwrap()
+            .finish_outputs(&output);
+    }
+    sent
+}
+
+/// Puts an item leaving the machine at `pos` onto the tile on its `side`,
+/// returning whether there was space for it
+fn output_item(
+    commands: &mut Commands,
+    items_query: &Query<&Transform, With<Item>>,
+    tilemap: &mut Tilemap,
+    pos: IVec2,
+    side: Side,
+    item: Item,
+) -> bool {
+    let output_tile = pos + side.to_ivec2();
+    let output_pos = output_tile.as_vec2();
+    match tilemap.get_tile(output_tile) {
+        Some(Tile::Belt(..)) => tilemap
+            .lanes_mut()
+            .try_insert(output_tile, side, item)
+            .is_ok(),
+        Some(tile) if tile.blocks_items() => false,
+        _ => {
+            let is_clear = !items_query.iter().any(|t| {
+                t.translation.xy().distance_squared(output_pos) < OUTPUT_CLEARANCE.powi(2)
+            });
+            if is_clear {
+                spawn_item(commands, item, output_pos, Vec2::ZERO);
+            }
+            is_clear
+        }
+    }
+}
+
+/// Shows the items on belts that are on screen, reusing the same sprites
+/// every frame. Base items are drawn from the texture atlas, so they can be
+/// batched, and only combined items use their own images
+pub(crate) fn draw_lane_items_system(
+    mut commands: Commands,
+    tilemap: Res<Tilemap>,
+    mut images: ItemImages,
+    camera_query: Query<(&OrthographicProjection, &GlobalTransform), With<MainCamera>>,
+    mut atlas_query: Query<
+        (&mut Transform, &mut TextureAtlasSprite, &mut Visibility),
+        With<LaneItemSprite>,
+    >,
+    mut image_query: Query<
+        (&mut Transform, &mut Handle<Image>, &mut Visibility),
+        (With<LaneItemSprite>, Without<TextureAtlasSprite>),
+    >,
+) {
+    let (projection, camera_transform) = camera_query.single();
+    let (min, max) = camera_view_rect(projection, camera_transform);
+    let textures = tilemap.textures();
+    let (base, combined): (Vec<_>, Vec<_>) = tilemap
+        .lanes()
+        .items_in(min, max)
+        .partition(|(_, item)| item.texture(textures).is_some());
+
+    let mut base = base.into_iter();
+    for (mut transform, mut sprite, mut visibility) in atlas_query.iter_mut() {
+        match base.next() {
+            Some((pos, item)) => {
+                transform.translation = pos.extend(ITEM_Z);
+                sprite.index = item.texture(textures).unwrap();
+                visibility.is_visible = true;
+            }
+            None => visibility.is_visible = false,
+        }
+    }
+    // Spawn more sprites when there are more items than sprites
+    for (pos, item) in base {
+        commands
+            .spawn_bundle(SpriteSheetBundle {
+                sprite: TextureAtlasSprite {
+                    index: item.texture(textures).unwrap(),
+                    custom_size: Some(Vec2::splat(ITEM_SIZE)),
+                    ..default()
+                },
+                texture_atlas: tilemap.atlas().clone(),
+                transform: Transform::from_translation(pos.extend(ITEM_Z)),
+                ..default()
+            })
+            .insert(LaneItemSprite);
+    }
+
+    let mut combined = combined.into_iter();
+    for (mut transform, mut image, mut visibility) in image_query.iter_mut() {
+        match combined.next() {
+            Some((pos, item)) => {
+                transform.translation = pos.extend(ITEM_Z);
+                let new_image = images.get(&item, textures);
+                if *image != new_image {
+                    *image = new_image;
+                }
+                visibility.is_visible = true;
+            }
+            None => visibility.is_visible = false,
+        }
+    }
+    for (pos, item) in combined {
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    custom_size: Some(Vec2::splat(ITEM_SIZE)),
+                    ..default()
+                },
+                texture: images.get(&item, textures),
+                transform: Transform::from_translation(pos.extend(ITEM_Z)),
+                ..default()
+            })
+            .insert(LaneItemSprite);
+    }
 }
@@ -0,0 +1,218 @@
+use super::{Item, ITEM_SIZE};
+use crate::{
+    prelude::*,
+    tilemap::{TextureMap, Tilemap},
+};
+use bevy::{
+    ecs::system::SystemParam,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+    sprite::Rect,
+    utils::HashMap,
+};
+use std::marker::PhantomData;
+
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ItemImageCache>()
+            .add_system_set(SystemSet::on_update(AppState::Game).with_system(item_sprite_system))
+            .add_system_set(SystemSet::on_exit(AppState::Game).with_system(clear_cache_system));
+    }
+}
+
+/// Width and height of every item's image, in pixels. Base items are drawn
+/// at four times their size, so items combined a couple of times over still
+/// have a pixel for each of their components' pixels
+const IMAGE_SIZE: usize = 24;
+const BYTES_PER_PIXEL: usize = 4;
+/// Drawn behind the components of combined items. Nested items draw theirs
+/// on top, so the deeper a component is the darker it is behind it
+const BACKDROP_COLOR: [u8; 4] = [0, 0, 0, 96];
+/// Space between a combined item's edge and its components, as a fraction
+/// of its width
+const BACKDROP_MARGIN: f32 = 1.0 / 12.0;
+
+/// The image made for each kind of item that has been seen so far this game,
+/// which is emptied when the game is left so images aren't kept forever
+#[derive(Debug, Default)]
+pub struct ItemImageCache(HashMap<Item, Handle<Image>>);
+
+/// Gets the image for an item, making it from the base items' sprites the
+/// first time an item of its kind is drawn
+#[derive(SystemParam)]
+pub struct ItemImages<'w, 's> {
+    cache: ResMut<'w, ItemImageCache>,
+    images: ResMut<'w, Assets<Image>>,
+    atlases: Res<'w, Assets<TextureAtlas>>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+
+impl ItemImages<'_, '_> {
+    /// The image for `item`, drawn from the sprites in the atlas of
+    /// `textures`
+    pub fn get(&mut self, item: &Item, textures: &TextureMap) -> Handle<Image> {
+        if let Some(handle) = self.cache.0.get(item) {
+            return handle.clone();
+        }
+        let atlas = self.atlases.get(&textures.atlas).unwrap();
+        let sprites = AtlasSprites {
+            atlas,
+            image: self.images.get(&atlas.texture).unwrap(),
+            textures,
+        };
+        let mut canvas = Canvas::default();
+        canvas.draw_item(item, Vec2::ZERO, IMAGE_SIZE as f32, &sprites);
+        let handle = self.images.add(canvas.into_image());
+        self.cache.0.insert(item.clone(), handle.clone());
+        handle
+    }
+}
+
+/// Where to read the base items' pixels from
+struct AtlasSprites<'a> {
+    atlas: &'a TextureAtlas,
+    image: &'a Image,
+    textures: &'a TextureMap,
+}
+
+impl AtlasSprites<'_> {
+    /// The color of the pixel at `pos` in the atlas
+    fn pixel(&self, pos: UVec2) -> [u8; 4] {
+        let i = (pos.y as usize * self.atlas.size.x as usize + pos.x as usize) * BYTES_PER_PIXEL;
+        self.image.data[i..i + BYTES_PER_PIXEL].try_into().unwrap()
+    }
+}
+
+/// An item's image being drawn, as rows of RGBA pixels from the top
+struct Canvas {
+    data: Vec<u8>,
+}
+
+impl Default for Canvas {
+    fn default() -> Self {
+        Canvas {
+            data: vec![0; IMAGE_SIZE * IMAGE_SIZE * BYTES_PER_PIXEL],
+        }
+    }
+}
+
+impl Canvas {
+    /// Draws `item` into the square with its top left corner at `min` and
+    /// `size` pixels wide. Combined items lay out their components in a
+    /// grid, filling it row by row
+    fn draw_item(&mut self, item: &Item, min: Vec2, size: f32, sprites: &AtlasSprites) {
+        let components = item.components();
+        if components.is_empty() {
+            let index = item.texture(sprites.textures).unwrap();
+            let source = sprites.atlas.textures[index];
+            self.fill(min, size, |uv| {
+                let pos = source.min + (uv * (source.max - source.min)).floor();
+                sprites.pixel(pos.as_uvec2())
+            });
+            return;
+        }
+        self.fill(min, size, |_| BACKDROP_COLOR);
+        let margin = size * BACKDROP_MARGIN;
+        let inner = size - 2.0 * margin;
+        let columns = (components.len() as f32).sqrt().ceil();
+        let rows = (components.len() as f32 / columns).ceil();
+        let cell = inner / columns;
+        // Fewer rows than columns leave space, which is split above and below
+        let top_left = min + Vec2::new(margin, margin + (inner - rows * cell) / 2.0);
+        for (i, component) in components.iter().enumerate() {
+            let grid = Vec2::new((i as f32 % columns).floor(), (i as f32 / columns).floor());
+            self.draw_item(component, top_left + grid * cell, cell, sprites);
+        }
+    }
+
+    /// Draws over each pixel whose center is in the square with its top left
+    /// corner at `min` and `size` pixels wide, with the color `color` gives
+    /// for how far across the square the center is, from 0 to 1
+    fn fill(&mut self, min: Vec2, size: f32, color: impl Fn(Vec2) -> [u8; 4]) {
+        let rect = Rect {
+            min: min.floor().max(Vec2::ZERO),
+            max: (min + Vec2::splat(size))
+                .ceil()
+                .min(Vec2::splat(IMAGE_SIZE as f32)),
+        };
+        for y in rect.min.y as usize..rect.max.y as usize {
+            for x in rect.min.x as usize..rect.max.x as usize {
+                let uv = (Vec2::new(x as f32, y as f32) + 0.5 - min) / size;
+                if uv.cmpge(Vec2::ZERO).all() && uv.cmplt(Vec2::ONE).all() {
+                    let i = (y * IMAGE_SIZE + x) * BYTES_PER_PIXEL;
+                    let pixel = &mut self.data[i..i + BYTES_PER_PIXEL];
+                    let bottom = (&*pixel).try_into().unwrap();
+                    pixel.copy_from_slice(&blend(color(uv), bottom));
+                }
+            }
+        }
+    }
+
+    fn into_image(self) -> Image {
+        let size = Extent3d {
+            width: IMAGE_SIZE as u32,
+            height: IMAGE_SIZE as u32,
+            depth_or_array_layers: 1,
+        };
+        Image::new(
+            size,
+            TextureDimension::D2,
+            self.data,
+            TextureFormat::Rgba8UnormSrgb,
+        )
+    }
+}
+
+/// Draws `top` over `bottom`, both RGBA colors that aren't premultiplied
+fn blend(top: [u8; 4], bottom: [u8; 4]) -> [u8; 4] {
+    let top_alpha = f32::from(top[3]) / 255.0;
+    let bottom_alpha = f32::from(bottom[3]) / 255.0 * (1.0 - top_alpha);
+    let alpha = top_alpha + bottom_alpha;
+    if alpha == 0.0 {
+        return [0; 4];
+    }
+    let mut color = [0, 0, 0, (alpha * 255.0).round() as u8];
+    for i in 0..3 {
+        let mixed = f32::from(top[i]) * top_alpha + f32::from(bottom[i]) * bottom_alpha;
+        color[i] = (mixed / alpha).round() as u8;
+    }
+    color
+}
+
+/// Gives items that aren't on belts their sprite, which for base items is
+/// from the texture atlas and for combined items is their own image
+fn item_sprite_system(
+    mut commands: Commands,
+    tilemap: Res<Tilemap>,
+    mut images: ItemImages,
+    items_query: Query<(Entity, &Item), Added<Item>>,
+) {
+    let custom_size = Some(Vec2::splat(ITEM_SIZE));
+    for (entity, item) in items_query.iter() {
+        let mut entity = commands.entity(entity);
+        match item.texture(tilemap.textures()) {
+            Some(index) => entity.insert_bundle((
+                TextureAtlasSprite {
+                    index,
+                    custom_size,
+                    ..default()
+                },
+                tilemap.atlas().clone(),
+            )),
+            None => entity.insert_bundle((
+                Sprite {
+                    custom_size,
+                    ..default()
+                },
+                images.get(item, tilemap.textures()),
+            )),
+        };
+    }
+}
+
+fn clear_cache_system(mut cache: ResMut<ItemImageCache>) {
+    cache.0.clear();
+}
@@ -0,0 +1,132 @@
+use crate::{
+    items::Item,
+    tilemap::{BuildArea, Layout, MachineType, Terrain},
+};
+use bevy::{prelude::*, utils::HashMap};
+use serde::Deserialize;
+
+/// Level files bundled into the executable, in the order they are shown
+const BUILTIN_LEVELS: &[&str] = &[
+    include_str!("../assets/levels/01_first_steps.ron"),
+    include_str!("../assets/levels/02_slippery.ron"),
+    include_str!("../assets/levels/03_combination.ron"),
+];
+
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Levels::builtin());
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Level {
+    pub name: String,
+    pub description: String,
+    /// What has to be done to complete the level, or `None` if it can't be
+    /// completed
+    #[serde(default)]
+    pub goal: Option<Goal>,
+    /// Where machines can be placed, or anywhere if not given
+    #[serde(default)]
+    pub build_area: Option<BuildArea>,
+    /// How many of each machine can be placed, or `None` if there is no
+    /// limit on any of them. Machines a budget doesn't list can't be placed
+    #[serde(default)]
+    pub budget: Option<HashMap<MachineType, Allowance>>,
+    /// Terrain placed before the player starts building
+    #[serde(default)]
+    pub terrain: Vec<TerrainArea>,
+}
+
+/// How many of a machine a level's budget lets the player place
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Allowance {
+    Limit(u32),
+    Unlimited,
+}
+
+/// Delivering `count` of `item` to targets completes a level
+#[derive(Debug, Clone, Deserialize)]
+pub struct Goal {
+    pub item: Item,
+    pub count: u32,
+}
+
+/// A rectangle of terrain in a level
+#[derive(Debug, Deserialize)]
+pub struct TerrainArea {
+    pub terrain: Terrain,
+    /// Corners of the rectangle, both included in it
+    pub min: IVec2,
+    pub max: IVec2,
+}
+
+impl TerrainArea {
+    /// Every tile in the rectangle
+    pub fn tiles(&self) -> impl Iterator<Item = IVec2> + '_ {
+        (self.min.y..=self.max.y)
+            .flat_map(move |y| (self.min.x..=self.max.x).map(move |x| IVec2::new(x, y)))
+    }
+}
+
+/// Every level that can be chosen from the level select screen
+#[derive(Debug, Default)]
+pub struct Levels(Vec<Level>);
+
+impl Levels {
+    /// Only the level given, for playing a level that isn't built in
+    #[must_use]
+    pub fn single(level: Level) -> Self {
+        Levels(vec![level])
+    }
+
+    fn builtin() -> Self {
+        Levels(
+            BUILTIN_LEVELS
+                .iter()
+                .enumerate()
+                .map(|(index, source)| {
+                    ron::from_str(source)
+                        .unwrap_or_else(|e| panic!("Invalid builtin level {index}: {e}"))
+                })
+                .collect(),
+        )
+    }
+
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<&Level> {
+        self.0.get(index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Level> {
+        self.0.iter()
+    }
+}
+
+/// The game currently being played, or the one last played if outside of
+/// `AppState::Game`.
+/// Only exists once a game has been started.
+#[derive(Debug, Default)]
+pub struct Session {
+    /// Index of the level being played, or `None` for sandbox mode
+    pub level: Option<usize>,
+    /// Machines placed by the player, saved when leaving `AppState::Game`
+    pub layout: Layout,
+}
+
+impl Session {
+    #[must_use]
+    pub fn sandbox() -> Self {
+        Session::default()
+    }
+
+    #[must_use]
+    pub fn level(index: usize) -> Self {
+        Session {
+            level: Some(index),
+            ..default()
+        }
+    }
+}
@@ -0,0 +1,173 @@
+#![allow(clippy::type_complexity)]
+
+use bevy::{prelude::*, render::texture::ImageSettings};
+use bindings::{Action, ActionInput};
+use serde::{Deserialize, Serialize};
+use tilemap::GridPos;
+
+pub mod bindings;
+#[cfg(feature = "debug_overlay")]
+mod debug;
+pub mod direction;
+pub mod headless;
+pub mod items;
+mod levels;
+mod menu;
+mod placing;
+pub mod replay;
+mod rewind;
+mod saves;
+mod scores;
+pub mod simulation;
+pub mod stats;
+pub mod tilemap;
+mod ui;
+
+mod prelude {
+    pub use super::direction::*;
+    pub use super::{despawn_all_system, AppState, Click, MouseInput};
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum AppState {
+    LoadingAssets,
+    MainMenu,
+    LevelSelect,
+    Settings,
+    Game,
+    Results,
+    /// Asks whether to recover an autosave newer than the last save
+    Recover,
+}
+
+/// Runs the game
+pub fn run() {
+    let mut app = App::new();
+    app.insert_resource(ImageSettings::default_nearest())
+        .init_resource::<MouseInput>()
+        .add_state(AppState::LoadingAssets)
+        .add_plugins(DefaultPlugins)
+        .add_plugin(bindings::Plugin)
+        .add_plugin(items::Plugin)
+        .add_plugin(levels::Plugin)
+        .add_plugin(menu::Plugin)
+        .add_plugin(placing::Plugin)
+        .add_plugin(replay::Plugin)
+        .add_plugin(rewind::Plugin)
+        .add_plugin(saves::Plugin)
+        .add_plugin(scores::Plugin)
+        .add_plugin(simulation::Plugin)
+        .add_plugin(stats::Plugin)
+        .add_plugin(tilemap::Plugin)
+        .add_plugin(ui::Plugin)
+        .add_startup_system(startup_system)
+        .add_system_set_to_stage(
+            CoreStage::PreUpdate,
+            SystemSet::new().with_system(capture_mouse_input_system),
+        );
+    #[cfg(feature = "debug_overlay")]
+    app.add_plugin(debug::Plugin);
+    app.run();
+}
+
+#[derive(Component)]
+pub struct MainCamera;
+
+fn startup_system(mut commands: Commands) {
+    commands
+        .spawn_bundle(Camera2dBundle {
+            projection: OrthographicProjection {
+                scale: 0.0125,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(MainCamera);
+}
+
+/// Despawns every entity with the component `T`, along with their children
+pub fn despawn_all_system<T: Component>(mut commands: Commands, query: Query<Entity, With<T>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Inputs, captured in `CoreState::PreUpdate`
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct MouseInput {
+    /// The grid position of the mouse
+    pub pos: Option<GridPos>,
+    /// Which mouse action is held, if any.
+    /// Guaranteed to be `Click::None` when `pos` is `None`.
+    pub click: Click,
+}
+
+/// What a mouse click should do to the tile under the mouse
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Click {
+    #[default]
+    None,
+    /// Apply the selected tool
+    UseTool,
+    /// Delete whatever is there, regardless of the selected tool
+    Remove,
+}
+
+impl MouseInput {
+    /// Returns `Some` if the pointer is over the window and clicked,
+    /// otherwise returns `None`
+    pub fn clicked_pos(&self) -> Option<GridPos> {
+        self.pos.filter(|_| self.click != Click::None)
+    }
+}
+
+fn capture_mouse_input_system(
+    windows: Res<Windows>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    blocking_query: Query<(&Node, &GlobalTransform), With<ui::BlocksWorldInput>>,
+    actions: ActionInput,
+    state: Res<State<AppState>>,
+    mut mouse_input: ResMut<MouseInput>,
+) {
+    use tilemap::*;
+
+    if *state.current() != AppState::Game {
+        *mouse_input = MouseInput::default();
+        return;
+    }
+
+    let window = windows.get_primary();
+
+    let pos = window
+        .and_then(|w| w.cursor_position())
+        .filter(|&mouse_pos| {
+            !blocking_query.iter().any(|(node, transform)| {
+                let offset = mouse_pos - transform.translation().truncate();
+                offset.abs().cmple(node.size * 0.5).all()
+            })
+        })
+        .map(|screen_pos| {
+            let (camera, camera_transform) = camera_query.single();
+            screen_to_grid_pos(
+                ScreenToWorldInputs {
+                    window: window.unwrap(),
+                    camera,
+                    camera_transform,
+                },
+                screen_pos,
+            )
+        });
+
+    let is_held = |action| actions.pressed(action) || actions.just_pressed(action);
+    let click = if pos.is_none() {
+        Click::None
+    } else if is_held(Action::UseTool) {
+        Click::UseTool
+    } else if is_held(Action::Remove) {
+        Click::Remove
+    } else {
+        Click::None
+    };
+
+    *mouse_input = MouseInput { pos, click };
+}
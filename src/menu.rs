@@ -0,0 +1,77 @@
+use crate::{
+    bindings::{Action, ActionInput},
+    prelude::*,
+    ui::UiFont,
+};
+use bevy::{ecs::schedule::StateError, prelude::*};
+
+mod level_select;
+mod main_menu;
+mod recover;
+mod results;
+mod settings;
+
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(level_select::Plugin)
+            .add_plugin(main_menu::Plugin)
+            .add_plugin(recover::Plugin)
+            .add_plugin(results::Plugin)
+            .add_plugin(settings::Plugin)
+            .add_system_set(SystemSet::on_update(AppState::Game).with_system(back_system))
+            .add_system_set(SystemSet::on_update(AppState::LevelSelect).with_system(back_system))
+            .add_system_set(SystemSet::on_update(AppState::Results).with_system(back_system));
+    }
+}
+
+/// The root node of a menu screen, despawned when leaving its state
+#[derive(Debug, Component)]
+struct MenuRoot;
+
+const BACKGROUND_COLOR: Color = Color::rgb(0.05, 0.05, 0.05);
+const TITLE_SIZE: f32 = 48.0;
+
+/// Spawns a full screen menu with a title; `children` adds the contents below it
+fn spawn_menu(
+    commands: &mut Commands,
+    font: &UiFont,
+    title: &str,
+    children: impl FnOnce(&mut ChildBuilder),
+) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: BACKGROUND_COLOR.into(),
+            ..default()
+        })
+        .insert(MenuRoot)
+        .with_children(|menu| {
+            menu.spawn_bundle(
+                font.text(title, TITLE_SIZE, crate::ui::TEXT_COLOR)
+                    .with_style(Style {
+                        margin: UiRect::all(Val::Px(20.0)),
+                        ..default()
+                    }),
+            );
+            children(menu);
+        });
+}
+
+/// Returns to the main menu on [`Action::Back`], unless something else has
+/// already changed the state this frame
+fn back_system(actions: ActionInput, mut state: ResMut<State<AppState>>) {
+    if actions.just_pressed(Action::Back) {
+        match state.set(AppState::MainMenu) {
+            Err(StateError::StateAlreadyQueued) => {}
+            result => result.unwrap(),
+        }
+    }
+}
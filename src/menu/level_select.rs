@@ -0,0 +1,55 @@
+use super::{spawn_menu, MenuRoot};
+use crate::{
+    levels::{Levels, Session},
+    prelude::*,
+    ui::{spawn_text_button, UiFont, DISABLED_TEXT_COLOR},
+};
+use bevy::prelude::*;
+
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(AppState::LevelSelect).with_system(setup_system))
+            .add_system_set(SystemSet::on_update(AppState::LevelSelect).with_system(button_system))
+            .add_system_set(
+                SystemSet::on_exit(AppState::LevelSelect)
+                    .with_system(despawn_all_system::<MenuRoot>),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, Component)]
+enum LevelButton {
+    Level(usize),
+    Back,
+}
+
+fn setup_system(mut commands: Commands, font: Res<UiFont>, levels: Res<Levels>) {
+    spawn_menu(&mut commands, &font, "Choose Level", |menu| {
+        for (index, level) in levels.iter().enumerate() {
+            spawn_text_button(menu, &font, &level.name, true).insert(LevelButton::Level(index));
+            menu.spawn_bundle(font.text(&level.description, 16.0, DISABLED_TEXT_COLOR));
+        }
+        spawn_text_button(menu, &font, "Back", true).insert(LevelButton::Back);
+    });
+}
+
+fn button_system(
+    mut commands: Commands,
+    interaction_query: Query<(&Interaction, &LevelButton), Changed<Interaction>>,
+    mut state: ResMut<State<AppState>>,
+) {
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        match *button {
+            LevelButton::Level(index) => {
+                commands.insert_resource(Session::level(index));
+                state.set(AppState::Game).unwrap();
+            }
+            LevelButton::Back => state.set(AppState::MainMenu).unwrap(),
+        }
+    }
+}
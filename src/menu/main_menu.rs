@@ -0,0 +1,77 @@
+use super::{spawn_menu, MenuRoot};
+use crate::{
+    levels::{Levels, Session},
+    prelude::*,
+    ui::{spawn_text_button, UiFont},
+};
+use bevy::{app::AppExit, prelude::*};
+
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(AppState::MainMenu).with_system(setup_system))
+            .add_system_set(SystemSet::on_update(AppState::MainMenu).with_system(button_system))
+            .add_system_set(
+                SystemSet::on_exit(AppState::MainMenu).with_system(despawn_all_system::<MenuRoot>),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, Component)]
+enum MenuButton {
+    NewSandbox,
+    Continue,
+    ChooseLevel,
+    Settings,
+    Quit,
+}
+
+fn setup_system(
+    mut commands: Commands,
+    font: Res<UiFont>,
+    levels: Res<Levels>,
+    session: Option<Res<Session>>,
+) {
+    let continue_label = match session.as_ref().map(|s| s.level) {
+        Some(Some(level)) => format!("Continue: {}", levels.get(level).unwrap().name),
+        Some(None) => "Continue Sandbox".to_owned(),
+        None => "Continue".to_owned(),
+    };
+    spawn_menu(&mut commands, &font, "Multifactory", |menu| {
+        use MenuButton::*;
+
+        for (button, label, enabled) in [
+            (NewSandbox, "New Sandbox", true),
+            (Continue, &continue_label, session.is_some()),
+            (ChooseLevel, "Choose Level", true),
+            (Settings, "Settings", true),
+            (Quit, "Quit", true),
+        ] {
+            spawn_text_button(menu, &font, label, enabled).insert(button);
+        }
+    });
+}
+
+fn button_system(
+    mut commands: Commands,
+    interaction_query: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    mut state: ResMut<State<AppState>>,
+    mut app_exit: EventWriter<AppExit>,
+) {
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        match button {
+            MenuButton::NewSandbox => {
+                commands.insert_resource(Session::sandbox());
+                state.set(AppState::Game).unwrap();
+            }
+            MenuButton::Continue => state.set(AppState::Game).unwrap(),
+            MenuButton::ChooseLevel => state.set(AppState::LevelSelect).unwrap(),
+            MenuButton::Settings => state.set(AppState::Settings).unwrap(),
+            MenuButton::Quit => app_exit.send(AppExit),
+        }
+    }
+}
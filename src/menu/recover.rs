@@ -0,0 +1,81 @@
+use super::{spawn_menu, MenuRoot};
+use crate::{
+    levels::Levels,
+    prelude::*,
+    saves::{describe_age, Recovery},
+    ui::{spawn_text_button, UiFont, DISABLED_TEXT_COLOR, TEXT_COLOR},
+};
+use bevy::prelude::*;
+
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(AppState::Recover).with_system(setup_system))
+            .add_system_set(SystemSet::on_update(AppState::Recover).with_system(button_system))
+            .add_system_set(
+                SystemSet::on_exit(AppState::Recover).with_system(despawn_all_system::<MenuRoot>),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, Component)]
+enum RecoverButton {
+    Recover,
+    Discard,
+}
+
+fn setup_system(
+    mut commands: Commands,
+    font: Res<UiFont>,
+    levels: Res<Levels>,
+    recovery: Res<Recovery>,
+) {
+    let autosave = &recovery.autosave;
+    let game = match autosave.level.and_then(|index| levels.get(index)) {
+        Some(level) => level.name.clone(),
+        None => "Sandbox".to_owned(),
+    };
+    let last_save = match recovery.last_save {
+        0 => "The game has never been saved".to_owned(),
+        saved_at => format!("Last saved {}", describe_age(saved_at)),
+    };
+    spawn_menu(&mut commands, &font, "Recover Autosave?", |menu| {
+        menu.spawn_bundle(font.text(
+            format!(
+                "{game}, autosaved {} with {} machines",
+                describe_age(autosave.saved_at),
+                autosave.layout.machines.len()
+            ),
+            20.0,
+            TEXT_COLOR,
+        ));
+        menu.spawn_bundle(font.text(last_save, 20.0, DISABLED_TEXT_COLOR));
+        spawn_text_button(menu, &font, "Recover", true).insert(RecoverButton::Recover);
+        spawn_text_button(menu, &font, "Discard", true).insert(RecoverButton::Discard);
+    });
+}
+
+fn button_system(
+    mut commands: Commands,
+    interaction_query: Query<(&Interaction, &RecoverButton), Changed<Interaction>>,
+    recovery: Res<Recovery>,
+    mut state: ResMut<State<AppState>>,
+) {
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        match button {
+            RecoverButton::Recover => {
+                commands.insert_resource(recovery.autosave.session());
+                state.set(AppState::Game).unwrap();
+            }
+            RecoverButton::Discard => {
+                recovery.discard();
+                state.set(AppState::MainMenu).unwrap();
+            }
+        }
+        commands.remove_resource::<Recovery>();
+    }
+}
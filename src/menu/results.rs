@@ -0,0 +1,120 @@
+use super::{spawn_menu, MenuRoot};
+use crate::{
+    levels::{Levels, Session},
+    prelude::*,
+    scores::{LevelResults, Score},
+    simulation::TICKS_PER_SECOND,
+    ui::{spawn_text_button, UiFont, DISABLED_TEXT_COLOR, TEXT_COLOR},
+};
+use bevy::prelude::*;
+
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(AppState::Results).with_system(setup_system))
+            .add_system_set(SystemSet::on_update(AppState::Results).with_system(button_system))
+            .add_system_set(
+                SystemSet::on_exit(AppState::Results).with_system(despawn_all_system::<MenuRoot>),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, Component)]
+enum ResultsButton {
+    Retry,
+    NextLevel,
+    ChooseLevel,
+}
+
+/// One part of a [`Score`], formatted for the results screen
+struct Row {
+    name: &'static str,
+    value: String,
+    best: Option<String>,
+    /// Whether this beat the previous best
+    improved: bool,
+}
+
+fn rows(score: Score, previous: Option<Score>) -> [Row; 4] {
+    let seconds = |ticks: u64| format!("{:.1}s", ticks as f32 / TICKS_PER_SECOND as f32);
+    let per_minute = |throughput: f32| format!("{throughput:.1} per minute");
+    [
+        Row {
+            name: "Machines used",
+            value: score.machines.to_string(),
+            best: previous.map(|p| p.machines.to_string()),
+            improved: previous.is_some_and(|p| score.machines < p.machines),
+        },
+        Row {
+            name: "Tiles covered",
+            value: score.tiles.to_string(),
+            best: previous.map(|p| p.tiles.to_string()),
+            improved: previous.is_some_and(|p| score.tiles < p.tiles),
+        },
+        Row {
+            name: "Time",
+            value: seconds(score.ticks),
+            best: previous.map(|p| seconds(p.ticks)),
+            improved: previous.is_some_and(|p| score.ticks < p.ticks),
+        },
+        Row {
+            name: "Throughput",
+            value: per_minute(score.throughput),
+            best: previous.map(|p| per_minute(p.throughput)),
+            improved: previous.is_some_and(|p| score.throughput > p.throughput),
+        },
+    ]
+}
+
+fn setup_system(
+    mut commands: Commands,
+    font: Res<UiFont>,
+    levels: Res<Levels>,
+    results: Res<LevelResults>,
+) {
+    let level = levels.get(results.level).unwrap();
+    let has_next = levels.get(results.level + 1).is_some();
+    spawn_menu(&mut commands, &font, "Level Complete", |menu| {
+        menu.spawn_bundle(font.text(&level.name, 24.0, DISABLED_TEXT_COLOR));
+        for row in rows(results.score, results.previous_best) {
+            let best = match (row.best, row.improved) {
+                (None, _) => "   First completion".to_owned(),
+                (Some(_), true) => "   New best!".to_owned(),
+                (Some(best), false) => format!("   Best: {best}"),
+            };
+            menu.spawn_bundle(TextBundle::from_sections([
+                TextSection::new(
+                    format!("{}: {}", row.name, row.value),
+                    font.style(20.0, TEXT_COLOR),
+                ),
+                TextSection::new(best, font.style(20.0, DISABLED_TEXT_COLOR)),
+            ]));
+        }
+        spawn_text_button(menu, &font, "Retry", true).insert(ResultsButton::Retry);
+        spawn_text_button(menu, &font, "Next Level", has_next).insert(ResultsButton::NextLevel);
+        spawn_text_button(menu, &font, "Choose Level", true).insert(ResultsButton::ChooseLevel);
+    });
+}
+
+fn button_system(
+    mut commands: Commands,
+    interaction_query: Query<(&Interaction, &ResultsButton), Changed<Interaction>>,
+    results: Res<LevelResults>,
+    mut state: ResMut<State<AppState>>,
+) {
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        match button {
+            // The session still has the layout, so this replays the same solution
+            ResultsButton::Retry => state.set(AppState::Game).unwrap(),
+            ResultsButton::NextLevel => {
+                commands.insert_resource(Session::level(results.level + 1));
+                state.set(AppState::Game).unwrap();
+            }
+            ResultsButton::ChooseLevel => state.set(AppState::LevelSelect).unwrap(),
+        }
+    }
+}
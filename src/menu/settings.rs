@@ -0,0 +1,198 @@
+use super::{spawn_menu, MenuRoot};
+use crate::{
+    bindings::{Action, ActionInput, Binding, Bindings},
+    prelude::*,
+    ui::{spawn_small_text_button, spawn_text_button, UiFont, TEXT_COLOR},
+};
+use bevy::prelude::*;
+
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Rebinding>()
+            .add_system_set(SystemSet::on_enter(AppState::Settings).with_system(setup_system))
+            .add_system_set(
+                SystemSet::on_update(AppState::Settings)
+                    .with_system(back_system.before(rebind_system))
+                    .with_system(rebind_system.before(button_system))
+                    .with_system(button_system)
+                    .with_system(binding_label_system.after(button_system)),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::Settings).with_system(despawn_all_system::<MenuRoot>),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, Component)]
+enum SettingsButton {
+    Binding(BindingSlot),
+    ResetBindings,
+    Back,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct BindingSlot {
+    action: Action,
+    slot: usize,
+}
+
+/// The binding slot waiting for the player to press something
+#[derive(Debug, Default)]
+struct Rebinding(Option<BindingSlot>);
+
+const BINDING_LIST_HEIGHT: f32 = 420.0;
+const UNBOUND_LABEL: &str = "-";
+const WAITING_LABEL: &str = "Press a key...";
+
+fn setup_system(
+    mut commands: Commands,
+    font: Res<UiFont>,
+    bindings: Res<Bindings>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    rebinding.0 = None;
+    spawn_menu(&mut commands, &font, "Settings", |menu| {
+        menu.spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Auto, Val::Px(BINDING_LIST_HEIGHT)),
+                flex_direction: FlexDirection::ColumnReverse,
+                flex_wrap: FlexWrap::Wrap,
+                justify_content: JustifyContent::FlexEnd,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .with_children(|list| {
+            for action in Action::all() {
+                spawn_binding_row(list, &font, &bindings, action);
+            }
+        });
+        spawn_text_button(menu, &font, "Reset Controls", true)
+            .insert(SettingsButton::ResetBindings);
+        spawn_text_button(menu, &font, "Back", true).insert(SettingsButton::Back);
+    });
+}
+
+fn spawn_binding_row(list: &mut ChildBuilder, font: &UiFont, bindings: &Bindings, action: Action) {
+    list.spawn_bundle(NodeBundle {
+        style: Style {
+            flex_direction: FlexDirection::Row,
+            align_items: AlignItems::Center,
+            margin: UiRect::new(Val::Px(20.0), Val::Px(20.0), Val::Px(0.0), Val::Px(0.0)),
+            ..default()
+        },
+        color: Color::NONE.into(),
+        ..default()
+    })
+    .with_children(|row| {
+        row.spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Px(140.0), Val::Auto),
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .with_children(|label| {
+            label.spawn_bundle(font.text(action.to_string(), 18.0, TEXT_COLOR));
+        });
+        for (slot, binding) in bindings.get(action).into_iter().enumerate() {
+            spawn_small_text_button(row, font, &binding_label(binding))
+                .insert(SettingsButton::Binding(BindingSlot { action, slot }));
+        }
+    });
+}
+
+fn binding_label(binding: Option<Binding>) -> String {
+    binding.map_or_else(|| UNBOUND_LABEL.to_owned(), |b| b.to_string())
+}
+
+fn button_system(
+    interaction_query: Query<(&Interaction, &SettingsButton), Changed<Interaction>>,
+    mut state: ResMut<State<AppState>>,
+    mut bindings: ResMut<Bindings>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        match *button {
+            SettingsButton::Binding(slot) => rebinding.0 = Some(slot),
+            SettingsButton::ResetBindings => {
+                *bindings = Bindings::default();
+                bindings.save();
+                rebinding.0 = None;
+            }
+            SettingsButton::Back => state.set(AppState::MainMenu).unwrap(),
+        }
+    }
+}
+
+/// Returns to the main menu, unless a binding is waiting for input
+fn back_system(
+    actions: ActionInput,
+    rebinding: Res<Rebinding>,
+    mut state: ResMut<State<AppState>>,
+) {
+    if rebinding.0.is_none() && actions.just_pressed(Action::Back) {
+        state.set(AppState::MainMenu).unwrap();
+    }
+}
+
+/// Binds the next key or mouse button pressed to the slot waiting for one.
+/// Escape cancels and backspace or delete unbinds the slot
+fn rebind_system(
+    keys: Res<Input<KeyCode>>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    mut bindings: ResMut<Bindings>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    let Some(BindingSlot { action, slot }) = rebinding.0 else {
+        return;
+    };
+    let new_binding = match keys.get_just_pressed().next() {
+        Some(KeyCode::Escape) => {
+            rebinding.0 = None;
+            return;
+        }
+        Some(KeyCode::Back | KeyCode::Delete) => None,
+        Some(&key) => Some(Binding::Key(key)),
+        None => match mouse_buttons.get_just_pressed().next() {
+            Some(&button) => Some(Binding::Mouse(button)),
+            None => return,
+        },
+    };
+    bindings.set(action, slot, new_binding);
+    bindings.save();
+    rebinding.0 = None;
+}
+
+fn binding_label_system(
+    bindings: Res<Bindings>,
+    rebinding: Res<Rebinding>,
+    button_query: Query<(&SettingsButton, &Children)>,
+    mut text_query: Query<&mut Text>,
+) {
+    if !bindings.is_changed() && !rebinding.is_changed() {
+        return;
+    }
+    for (button, children) in button_query.iter() {
+        let SettingsButton::Binding(slot) = *button else {
+            continue;
+        };
+        let label = if rebinding.0 == Some(slot) {
+            WAITING_LABEL.to_owned()
+        } else {
+            binding_label(bindings.get(slot.action)[slot.slot])
+        };
+        for &child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(child) {
+                text.sections[0].value = label.clone();
+            }
+        }
+    }
+}
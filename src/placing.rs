@@ -1,27 +1,61 @@
-use crate::{prelude::*, tilemap::MachineType};
-use bevy::prelude::*;
+use crate::{
+    bindings::{Action, ActionInput},
+    levels::{Allowance, Level, Levels, Session},
+    prelude::*,
+    tilemap::{load_session_system, MachineType, Tilemap},
+};
+use bevy::{prelude::*, utils::HashMap};
+use filter::{set_filter_system, SetFilter, SorterFilter};
+use serde::{Deserialize, Serialize};
+use world::use_tool_system;
 
+pub(crate) mod filter;
+mod inspect;
 pub mod toolbar;
-mod world;
+pub(crate) mod world;
 
 pub struct Plugin;
 
 impl bevy::prelude::Plugin for Plugin {
     fn build(&self, app: &mut App) {
-        app.add_plugin(toolbar::Plugin)
-            .add_plugin(world::Plugin)
-            .init_resource::<Tool>()
+        app.add_plugin(HeadlessPlugin)
+            .add_plugin(filter::Plugin)
+            .add_plugin(inspect::Plugin)
+            .add_plugin(toolbar::Plugin)
+            .add_plugin(world::Plugin);
+    }
+}
+
+/// Uses the selected tool on the tile under the mouse, without the toolbar,
+/// cursor or any other UI
+pub(crate) struct HeadlessPlugin;
+
+impl bevy::prelude::Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Tool>()
             .init_resource::<ToolDirection>()
+            .init_resource::<Budget>()
+            .init_resource::<SorterFilter>()
+            .add_event::<SetFilter>()
+            .add_system_set(
+                SystemSet::on_enter(AppState::Game)
+                    .with_system(reset_tool_system)
+                    .with_system(reset_budget_system.after(load_session_system)),
+            )
             .add_system_set(
-                SystemSet::on_update(AppState::Game).with_system(change_placing_direction_system),
+                SystemSet::on_update(AppState::Game)
+                    .with_system(change_placing_direction_system)
+                    .with_system(set_filter_system)
+                    .with_system(use_tool_system.after(set_filter_system)),
             );
     }
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Tool {
     #[default]
     Delete,
+    Inspect,
     Place(MachineType),
 }
 
@@ -41,20 +75,108 @@ impl Tool {
             _ => Vec2::ZERO,
         }
     }
+
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Tool::Delete => "Delete",
+            Tool::Inspect => "Inspect",
+            Tool::Place(machine) => machine.name(),
+        }
+    }
+
+    #[must_use]
+    pub fn description(self) -> &'static str {
+        match self {
+            Tool::Delete => "Removes machines.",
+            Tool::Inspect => {
+                "Shows what the machine under the mouse is doing. Click to keep showing it."
+            }
+            Tool::Place(machine) => machine.description(),
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
-pub struct ToolDirection(Side);
+pub struct ToolDirection(pub(crate) Side);
+
+/// How many more of each machine can be placed, or `None` if there is no
+/// limit on any of them. Machines without an entry can't be placed
+#[derive(Debug, Default)]
+pub struct Budget(Option<HashMap<MachineType, Allowance>>);
+
+impl Budget {
+    /// The budget of `level`, or no limits in sandbox mode
+    #[must_use]
+    pub fn new(level: Option<&Level>) -> Self {
+        Budget(level.and_then(|level| level.budget.clone()))
+    }
+
+    /// How many more of a machine can be placed, or `None` if there is no
+    /// limit
+    #[must_use]
+    pub fn remaining(&self, machine: MachineType) -> Option<u32> {
+        match self.0.as_ref()?.get(&machine) {
+            Some(Allowance::Limit(n)) => Some(*n),
+            Some(Allowance::Unlimited) => None,
+            None => Some(0),
+        }
+    }
+
+    #[must_use]
+    pub fn can_afford(&self, machine: MachineType) -> bool {
+        self.remaining(machine).is_none_or(|n| n > 0)
+    }
+
+    pub fn spend(&mut self, machine: MachineType) {
+        if let Some(n) = self.limit_mut(machine) {
+            *n = n.saturating_sub(1);
+        }
+    }
+
+    pub fn refund(&mut self, machine: MachineType) {
+        if let Some(n) = self.limit_mut(machine) {
+            *n += 1;
+        }
+    }
+
+    /// How many more of a machine can be placed, if there is a limit
+    fn limit_mut(&mut self, machine: MachineType) -> Option<&mut u32> {
+        let budget = self.0.as_mut()?;
+        match budget.entry(machine).or_insert(Allowance::Limit(0)) {
+            Allowance::Limit(n) => Some(n),
+            Allowance::Unlimited => None,
+        }
+    }
+}
+
+/// The toolbar always starts with the first tool selected, so match it
+pub(crate) fn reset_tool_system(mut tool: ResMut<Tool>) {
+    *tool = Tool::default();
+}
+
+/// Starts with the level's budget, less whatever the loaded layout used
+pub(crate) fn reset_budget_system(
+    mut budget: ResMut<Budget>,
+    session: Res<Session>,
+    levels: Res<Levels>,
+    tilemap: Res<Tilemap>,
+) {
+    *budget = Budget::new(session.level.and_then(|index| levels.get(index)));
+    for placed in tilemap.layout().machines {
+        budget.spend(placed.machine);
+    }
+}
 
-fn change_placing_direction_system(
+pub(crate) fn change_placing_direction_system(
     mut placing_direction: ResMut<ToolDirection>,
-    keys: Res<Input<KeyCode>>,
+    actions: ActionInput,
 ) {
-    if keys.any_just_released([KeyCode::A, KeyCode::Left]) {
+    if actions.just_released(Action::RotateLeft) {
         placing_direction.0 = placing_direction.0.rotate_left();
     }
 
-    if keys.any_just_released([KeyCode::D, KeyCode::Right]) {
+    if actions.just_released(Action::RotateRight) {
         placing_direction.0 = placing_direction.0.rotate_right();
     }
 }
@@ -0,0 +1,248 @@
+use super::{inspect::Pinned, Tool};
+use crate::{
+    items::{Item, ItemImages},
+    prelude::*,
+    stats::ItemEvent,
+    tilemap::{MachineType, Sorter, Tilemap},
+    ui::{BlocksWorldInput, UiFont, TEXT_COLOR},
+};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PickerItems>()
+            .add_system_set(SystemSet::on_enter(AppState::Game).with_system(setup_system))
+            .add_system_set(
+                SystemSet::on_update(AppState::Game)
+                    .with_system(click_system.before(set_filter_system))
+                    .with_system(show_system.after(set_filter_system))
+                    .with_system(seen_items_system)
+                    .with_system(
+                        spawn_buttons_system
+                            .after(seen_items_system)
+                            .after(show_system),
+                    ),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::Game).with_system(despawn_all_system::<FilterPicker>),
+            );
+    }
+}
+
+/// The filter new sorters are placed with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SorterFilter(pub Item);
+
+impl Default for SorterFilter {
+    fn default() -> Self {
+        SorterFilter(Item::A)
+    }
+}
+
+/// Changes the filter of the sorter at `sorter`, or of new sorters if `None`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SetFilter {
+    pub sorter: Option<IVec2>,
+    pub item: Item,
+}
+
+/// The items the picker offers, in the order they were first seen this game:
+/// the base items, then every item made or filtered for
+#[derive(Debug)]
+struct PickerItems(Vec<Item>);
+
+impl Default for PickerItems {
+    fn default() -> Self {
+        PickerItems(vec![Item::A, Item::B, Item::C, Item::D])
+    }
+}
+
+impl PickerItems {
+    fn add(&mut self, item: &Item) {
+        if !self.0.contains(item) {
+            self.0.push(item.clone());
+        }
+    }
+}
+
+/// Lets the player pick a sorter's filter, shown while placing sorters or
+/// inspecting one
+#[derive(Component)]
+struct FilterPicker;
+
+#[derive(Component)]
+pub(crate) struct FilterButton(Item);
+
+const PANEL_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.75);
+const DESELECTED_COLOR: Color = Color::rgb(0.5, 0.5, 0.5);
+const SELECTED_COLOR: Color = Color::WHITE;
+const BUTTON_SIZE: f32 = 32.0;
+/// Keeps the picker clear of the toolbar
+const PICKER_BOTTOM: f32 = 70.0;
+
+/// Whose filter the picker changes, or `None` if it is hidden. That is new
+/// sorters while placing them, or the pinned sorter while inspecting
+fn picker_target(tool: Tool, pinned: &Pinned, tilemap: &Tilemap) -> Option<Option<IVec2>> {
+    match tool {
+        Tool::Place(MachineType::Sorter) => Some(None),
+        Tool::Inspect => pinned
+            .0
+            .filter(|&pos| tilemap.machine::<Sorter>(pos).is_some())
+            .map(Some),
+        _ => None,
+    }
+}
+
+fn setup_system(mut commands: Commands, font: Res<UiFont>, mut items: ResMut<PickerItems>) {
+    *items = PickerItems::default();
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    right: Val::Px(8.0),
+                    bottom: Val::Px(PICKER_BOTTOM),
+                    ..default()
+                },
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(4.0)),
+                display: Display::None,
+                ..default()
+            },
+            color: PANEL_COLOR.into(),
+            ..default()
+        })
+        .insert(FilterPicker)
+        .insert(BlocksWorldInput)
+        .with_children(|picker| {
+            picker.spawn_bundle(TextBundle {
+                style: Style {
+                    margin: UiRect::new(Val::Px(4.0), Val::Px(8.0), Val::Px(0.0), Val::Px(0.0)),
+                    ..default()
+                },
+                ..font.text("Filter", 16.0, TEXT_COLOR)
+            });
+        });
+}
+
+/// Offers every item made in the game
+fn seen_items_system(mut item_events: EventReader<ItemEvent>, mut items: ResMut<PickerItems>) {
+    for event in item_events.iter() {
+        items.add(&event.item);
+    }
+}
+
+/// Adds a button for each item the picker doesn't have one for yet. Items
+/// are only ever added, so these are the last ones
+fn spawn_buttons_system(
+    mut commands: Commands,
+    items: Res<PickerItems>,
+    tilemap: Res<Tilemap>,
+    mut images: ItemImages,
+    picker_query: Query<Entity, With<FilterPicker>>,
+    button_query: Query<(), With<FilterButton>>,
+) {
+    let Ok(picker) = picker_query.get_single() else {
+        return;
+    };
+    let shown = button_query.iter().count();
+    if shown == items.0.len() {
+        return;
+    }
+    commands.entity(picker).with_children(|picker| {
+        for item in &items.0[shown..] {
+            picker
+                .spawn_bundle(ButtonBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(BUTTON_SIZE), Val::Px(BUTTON_SIZE)),
+                        margin: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    image: images.get(item, tilemap.textures()).into(),
+                    color: DESELECTED_COLOR.into(),
+                    ..default()
+                })
+                .insert(FilterButton(item.clone()));
+        }
+    });
+}
+
+/// Clicking an item sets it as the filter of whichever sorter the picker is
+/// for
+pub(crate) fn click_system(
+    tool: Res<Tool>,
+    pinned: Res<Pinned>,
+    tilemap: Res<Tilemap>,
+    button_query: Query<(&Interaction, &FilterButton), Changed<Interaction>>,
+    mut set_filter: EventWriter<SetFilter>,
+) {
+    let Some(sorter) = picker_target(*tool, &pinned, &tilemap) else {
+        return;
+    };
+    for (interaction, button) in button_query.iter() {
+        if *interaction == Interaction::Clicked {
+            set_filter.send(SetFilter {
+                sorter,
+                item: button.0.clone(),
+            });
+        }
+    }
+}
+
+pub(crate) fn set_filter_system(
+    mut set_filter: EventReader<SetFilter>,
+    mut sorter_filter: ResMut<SorterFilter>,
+    mut tilemap: ResMut<Tilemap>,
+) {
+    for event in set_filter.iter() {
+        match event.sorter {
+            Some(pos) => {
+                if let Some(sorter) = tilemap.machine_mut::<Sorter>(pos) {
+                    sorter.set_filter(event.item.clone());
+                }
+            }
+            None => sorter_filter.0 = event.item.clone(),
+        }
+    }
+}
+
+/// Shows the picker when there is a sorter to pick for, highlighting its
+/// filter, which is added to the picker if it isn't there yet
+fn show_system(
+    tool: Res<Tool>,
+    pinned: Res<Pinned>,
+    tilemap: Res<Tilemap>,
+    sorter_filter: Res<SorterFilter>,
+    mut items: ResMut<PickerItems>,
+    mut picker_query: Query<&mut Style, With<FilterPicker>>,
+    mut button_query: Query<(&FilterButton, &mut UiColor)>,
+) {
+    let mut style = picker_query.single_mut();
+    let Some(target) = picker_target(*tool, &pinned, &tilemap) else {
+        if style.display != Display::None {
+            style.display = Display::None;
+        }
+        return;
+    };
+    if style.display != Display::Flex {
+        style.display = Display::Flex;
+    }
+    let filter = match target {
+        Some(pos) => tilemap.machine::<Sorter>(pos).unwrap().filter(),
+        None => &sorter_filter.0,
+    };
+    items.add(filter);
+    for (button, mut color) in button_query.iter_mut() {
+        let new_color = if button.0 == *filter {
+            SELECTED_COLOR
+        } else {
+            DESELECTED_COLOR
+        };
+        if color.0 != new_color {
+            color.0 = new_color;
+        }
+    }
+}
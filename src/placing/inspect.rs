@@ -0,0 +1,263 @@
+use super::Tool;
+use crate::{
+    bindings::{Action, ActionInput},
+    items::{Item, SOURCE_INTERVAL},
+    prelude::*,
+    simulation::TICKS_PER_SECOND,
+    tilemap::*,
+    ui::{UiFont, DISABLED_TEXT_COLOR, TEXT_COLOR},
+};
+use bevy::{math::Vec3Swizzles, prelude::*};
+use std::fmt::Write;
+
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Pinned>()
+            .add_system_set(SystemSet::on_enter(AppState::Game).with_system(setup_system))
+            .add_system_set(
+                SystemSet::on_update(AppState::Game)
+                    .with_system(pin_system)
+                    .with_system(update_system.after(pin_system)),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::Game).with_system(despawn_all_system::<InspectPanel>),
+            );
+    }
+}
+
+#[derive(Component)]
+struct InspectPanel;
+
+/// The tile clicked on with [`Tool::Inspect`], shown instead of the one
+/// under the mouse
+#[derive(Debug, Default)]
+pub(crate) struct Pinned(pub(crate) Option<IVec2>);
+
+const PANEL_WIDTH: f32 = 260.0;
+const PANEL_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.75);
+
+fn setup_system(mut commands: Commands, font: Res<UiFont>, mut pinned: ResMut<Pinned>) {
+    pinned.0 = None;
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Px(0.0),
+                    top: Val::Px(0.0),
+                    ..default()
+                },
+                size: Size::new(Val::Px(PANEL_WIDTH), Val::Auto),
+                padding: UiRect::all(Val::Px(8.0)),
+                display: Display::None,
+                ..default()
+            },
+            color: PANEL_COLOR.into(),
+            ..default()
+        })
+        .insert(InspectPanel)
+        .with_children(|panel| {
+            panel.spawn_bundle(TextBundle {
+                text: Text::from_sections([
+                    TextSection::new("", font.style(20.0, TEXT_COLOR)),
+                    TextSection::new("", font.style(14.0, DISABLED_TEXT_COLOR)),
+                    TextSection::new("", font.style(16.0, TEXT_COLOR)),
+                ]),
+                style: Style {
+                    max_size: Size::new(Val::Px(PANEL_WIDTH - 16.0), Val::Undefined),
+                    ..default()
+                },
+                ..default()
+            });
+        });
+}
+
+/// Clicking a tile pins it, and clicking it again unpins it
+fn pin_system(
+    tool: Res<Tool>,
+    actions: ActionInput,
+    mouse_input: Res<MouseInput>,
+    mut pinned: ResMut<Pinned>,
+) {
+    if *tool != Tool::Inspect {
+        if pinned.0.is_some() {
+            pinned.0 = None;
+        }
+        return;
+    }
+    if let Some(pos) = mouse_input
+        .pos
+        .filter(|_| actions.just_pressed(Action::UseTool))
+    {
+        pinned.0 = match pinned.0 {
+            Some(tile) if tile == pos.tile => None,
+            _ => Some(pos.tile),
+        };
+    }
+}
+
+fn item_name(item: Option<&Item>) -> String {
+    item.map_or_else(|| "empty".to_owned(), Item::to_string)
+}
+
+/// How long combining or taking apart `item` takes, in seconds
+fn combine_seconds(item: &Item) -> f32 {
+    item.combine_ticks() as f32 / TICKS_PER_SECOND as f32
+}
+
+/// Describes a tile as the title, a subtitle and the body of the panel
+fn describe(tilemap: &Tilemap, pos: IVec2, items_on_tile: usize) -> [String; 3] {
+    let Some(tile) = tilemap.get_tile(tilemap.machine_pos(pos)) else {
+        return [
+            "Empty".to_owned(),
+            format!("\n({}, {})", pos.x, pos.y),
+            format!("\nItems: {items_on_tile}"),
+        ];
+    };
+
+    let mut subtitle = format!("\n({}, {})", pos.x, pos.y);
+    if let Some(facing) = tile.facing() {
+        let _ = write!(subtitle, ", facing {facing:?}");
+    }
+
+    let body = match tile {
+        Tile::Belt(..) | Tile::Ice(_) | Tile::Terrain(Terrain::Ice, _) => {
+            let status = match items_on_tile {
+                0 => "Idle".to_owned(),
+                1 => "Carrying 1 item".to_owned(),
+                n => format!("Carrying {n} items"),
+            };
+            format!("\nStatus: {status}")
+        }
+        Tile::Part(_) | Tile::Combiner(_) => {
+            let combiner = tilemap.combiner(pos).unwrap();
+            let makes = match combiner
+                .inputs()
+                .iter()
+                .cloned()
+                .collect::<Option<Vec<_>>>()
+            {
+                Some(inputs) => {
+                    let output = Item::combine(inputs);
+                    format!("{output} in {:.1}s", combine_seconds(&output))
+                }
+                None => "whatever fills every input, in order".to_owned(),
+            };
+            let status = match combiner.status() {
+                MachineStatus::Idle => "Idle".to_owned(),
+                MachineStatus::WaitingForInput => "Waiting for input".to_owned(),
+                MachineStatus::Working => {
+                    format!("Working, {:.0}%", combiner.progress() * 100.0)
+                }
+                MachineStatus::BlockedOutput => "Output blocked".to_owned(),
+            };
+            let inputs: Vec<_> = combiner
+                .inputs()
+                .iter()
+                .map(|i| item_name(i.as_ref()))
+                .collect();
+            format!(
+                "\nInputs: {}\nMakes: {makes}\nStatus: {status}",
+                inputs.join(", ")
+            )
+        }
+        Tile::Sorter(sorter) => {
+            let status = match sorter.status() {
+                MachineStatus::BlockedOutput => "Output blocked",
+                MachineStatus::Idle => "Idle",
+                _ => "Working",
+            };
+            format!(
+                "\nFilter: {}\nHolding: {}\nStatus: {status}",
+                sorter.filter(),
+                item_name(sorter.held())
+            )
+        }
+        Tile::Merger(merger) => {
+            let status = match merger.status() {
+                MachineStatus::BlockedOutput => "Output blocked",
+                MachineStatus::Idle => "Idle",
+                _ => "Working",
+            };
+            let inputs = merger
+                .input_sides()
+                .iter()
+                .zip(merger.inputs())
+                .map(|(side, item)| format!("{side:?} {}", item_name(item.as_ref())))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("\nInputs: {inputs}\nStatus: {status}")
+        }
+        Tile::Disassembler(disassembler) => {
+            let makes = disassembler.input().map_or_else(
+                || "the items anything combined was made from".to_owned(),
+                |input| {
+                    let outputs: Vec<_> = input.components().iter().map(Item::to_string).collect();
+                    format!("{} in {:.1}s", outputs.join(", "), combine_seconds(input))
+                },
+            );
+            let status = match disassembler.status() {
+                MachineStatus::Working if disassembler.input().is_some() => {
+                    format!("Working, {:.0}%", disassembler.progress() * 100.0)
+                }
+                MachineStatus::BlockedOutput => "Output blocked".to_owned(),
+                MachineStatus::Idle => "Idle".to_owned(),
+                _ => "Sending out items".to_owned(),
+            };
+            format!(
+                "\nInput: {}\nMakes: {makes}\nStatus: {status}",
+                item_name(disassembler.input())
+            )
+        }
+        Tile::Terrain(Terrain::Source(item), _) => format!(
+            "\nMakes {item} every {:.1}s",
+            SOURCE_INTERVAL as f32 / TICKS_PER_SECOND as f32
+        ),
+        Tile::Terrain(Terrain::Target, _) => {
+            "\nTakes in items that count towards the level's goal".to_owned()
+        }
+        Tile::Terrain(..) => "\nPart of the level; can't be built on or removed".to_owned(),
+    };
+    [tile.name().to_owned(), subtitle, body]
+}
+
+fn update_system(
+    tool: Res<Tool>,
+    pinned: Res<Pinned>,
+    mouse_input: Res<MouseInput>,
+    tilemap: Res<Tilemap>,
+    items_query: Query<&Transform, With<Item>>,
+    mut panel_query: Query<(&mut Style, &Children), With<InspectPanel>>,
+    mut text_query: Query<&mut Text>,
+) {
+    let (mut style, children) = panel_query.single_mut();
+    let inspected = pinned.0.or_else(|| {
+        mouse_input
+            .pos
+            .filter(|_| *tool == Tool::Inspect)
+            .map(|pos| pos.tile)
+    });
+    let Some(pos) = inspected else {
+        style.display = Display::None;
+        return;
+    };
+    style.display = Display::Flex;
+
+    let items_on_tile = items_query
+        .iter()
+        .filter(|t| world_to_grid_pos(t.translation.xy()).tile == pos)
+        .count()
+        + tilemap.lanes().items_on(pos);
+    let mut text = text_query.get_mut(children[0]).unwrap();
+    let mut info = describe(&tilemap, pos, items_on_tile);
+    if pinned.0.is_some() {
+        info[1] += " (pinned)";
+    }
+    for (section, value) in text.sections.iter_mut().zip(info) {
+        if section.value != value {
+            section.value = value;
+        }
+    }
+}
@@ -1,28 +1,99 @@
-use super::Tool;
-use crate::{prelude::*, tilemap::*};
-use bevy::prelude::*;
+use super::{world::use_tool_system, Budget, Tool};
+use crate::{
+    bindings::{Action, ActionInput, Binding, Bindings, TOOL_HOTKEYS},
+    prelude::*,
+    tilemap::*,
+    ui::{BlocksWorldInput, UiFont, DISABLED_TEXT_COLOR, TEXT_COLOR},
+};
+use bevy::{input::mouse::MouseWheel, prelude::*};
 
 pub struct Plugin;
 
 impl bevy::prelude::Plugin for Plugin {
     fn build(&self, app: &mut App) {
         app.add_system_set(SystemSet::on_enter(AppState::Game).with_system(setup_system))
-            .add_system_set(SystemSet::on_update(AppState::Game).with_system(change_tool_system));
+            .add_system_set(
+                SystemSet::on_exit(AppState::Game)
+                    .with_system(despawn_all_system::<Background>)
+                    .with_system(despawn_all_system::<Tooltip>),
+            )
+            .add_event::<SelectTool>()
+            .add_system_set(
+                SystemSet::on_update(AppState::Game)
+                    .with_system(click_tool_system.before(change_tool_system))
+                    .with_system(hotkey_tool_system.before(change_tool_system))
+                    .with_system(scroll_tool_system.before(change_tool_system))
+                    .with_system(change_tool_system.before(use_tool_system))
+                    .with_system(cursor_icon_system.after(change_tool_system))
+                    .with_system(icon_color_system.after(change_tool_system))
+                    .with_system(budget_counter_system)
+                    .with_system(tooltip_system),
+            );
     }
 }
 
 #[derive(Component)]
-struct ToolIcon {
+pub(crate) struct ToolIcon {
     tool: Tool,
+    slot: usize,
 }
 
+/// Shows how many more of a machine can be placed
+#[derive(Component)]
+struct BudgetCounter(MachineType);
+
+/// Selects the tool in a toolbar slot.
+/// Every way of choosing a tool goes through this, so the toolbar, the
+/// [`Tool`] resource and the cursor stay in sync
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SelectTool(usize);
+
 #[derive(Component)]
 pub struct Background;
 
+/// Describes the tool under the mouse
+#[derive(Component)]
+struct Tooltip;
+
 const DESELECTED_COLOR: Color = Color::rgb(0.5, 0.5, 0.5);
 const SELECTED_COLOR: Color = Color::WHITE;
+/// Color of machines that can't be placed because none are left
+const EXHAUSTED_COLOR: Color = Color::rgb(0.2, 0.2, 0.2);
+
+const TOOLTIP_COLOR: Color = Color::rgba(0.1, 0.1, 0.1, 0.9);
+const TOOLTIP_WIDTH: f32 = 240.0;
+/// Distance between the mouse and the bottom left corner of the tooltip
+const TOOLTIP_OFFSET: Vec2 = Vec2::new(12.0, 16.0);
+
+fn setup_system(mut commands: Commands, asset_server: Res<AssetServer>, font: Res<UiFont>) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                size: Size::new(Val::Px(TOOLTIP_WIDTH), Val::Auto),
+                padding: UiRect::all(Val::Px(6.0)),
+                display: Display::None,
+                ..default()
+            },
+            color: TOOLTIP_COLOR.into(),
+            ..default()
+        })
+        .insert(Tooltip)
+        .with_children(|tooltip| {
+            tooltip.spawn_bundle(TextBundle {
+                text: Text::from_sections([
+                    TextSection::new("", font.style(20.0, TEXT_COLOR)),
+                    TextSection::new("", font.style(14.0, DISABLED_TEXT_COLOR)),
+                    TextSection::new("", font.style(16.0, TEXT_COLOR)),
+                ]),
+                style: Style {
+                    max_size: Size::new(Val::Px(TOOLTIP_WIDTH - 12.0), Val::Undefined),
+                    ..default()
+                },
+                ..default()
+            });
+        });
 
-fn setup_system(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands
         .spawn_bundle(NodeBundle {
             style: Style {
@@ -41,18 +112,34 @@ fn setup_system(mut commands: Commands, asset_server: Res<AssetServer>) {
             ..default()
         })
         .insert(Background)
+        .insert(BlocksWorldInput)
         .with_children(|toolbar| {
             use MachineType::*;
             use Tool::*;
 
-            for (index, (tool, image)) in [
+            for (slot, (tool, image)) in [
                 (Delete, asset_server.load("ui/delete.png")),
+                (Inspect, asset_server.load("ui/inspect.png")),
                 (Place(Belt), asset_server.load("tiles/belt_0.png")),
                 (Place(Ice), asset_server.load("tiles/ice.png")),
                 (
                     Place(Combiner2x1),
                     asset_server.load("tiles/combiner2x1.png"),
                 ),
+                (
+                    Place(Combiner3x1),
+                    asset_server.load("tiles/combiner3x1.png"),
+                ),
+                (
+                    Place(Combiner2x2),
+                    asset_server.load("tiles/combiner2x2.png"),
+                ),
+                (Place(Sorter), asset_server.load("tiles/sorter.png")),
+                (Place(Merger), asset_server.load("tiles/merger.png")),
+                (
+                    Place(Disassembler),
+                    asset_server.load("tiles/disassembler.png"),
+                ),
             ]
             .into_iter()
             .enumerate()
@@ -68,14 +155,31 @@ fn setup_system(mut commands: Commands, asset_server: Res<AssetServer>) {
                             ..default()
                         },
                         image: image.into(),
-                        color: match index {
+                        color: match slot {
                             0 => SELECTED_COLOR,
                             _ => DESELECTED_COLOR,
                         }
                         .into(),
                         ..default()
                     })
-                    .insert(ToolIcon { tool });
+                    .insert(ToolIcon { tool, slot })
+                    .with_children(|icon| {
+                        if let Place(machine) = tool {
+                            icon.spawn_bundle(TextBundle {
+                                style: Style {
+                                    position_type: PositionType::Absolute,
+                                    position: UiRect {
+                                        right: Val::Px(2.0),
+                                        bottom: Val::Px(0.0),
+                                        ..default()
+                                    },
+                                    ..default()
+                                },
+                                ..font.text("", 16.0, TEXT_COLOR)
+                            })
+                            .insert(BudgetCounter(machine));
+                        }
+                    });
             }
         });
 }
@@ -84,37 +188,183 @@ impl Tool {
     fn icon(&self, textures: &TextureMap) -> usize {
         match self {
             Tool::Delete => textures.delete_tool,
+            Tool::Inspect => textures.inspect_tool,
             Tool::Place(MachineType::Belt) => textures.belt,
             Tool::Place(MachineType::Ice) => textures.ice,
             Tool::Place(MachineType::Combiner2x1) => textures.combiner2x1,
+            Tool::Place(MachineType::Combiner3x1) => textures.combiner3x1,
+            Tool::Place(MachineType::Combiner2x2) => textures.combiner2x2,
+            Tool::Place(MachineType::Sorter) => textures.sorter,
+            Tool::Place(MachineType::Merger) => textures.merger,
+            Tool::Place(MachineType::Disassembler) => textures.disassembler,
         }
     }
 }
 
-fn change_tool_system(
-    interaction_query: Query<(&Interaction, Entity), (With<ToolIcon>, Changed<Interaction>)>,
-    mut icon_query: Query<(&ToolIcon, &mut UiColor)>,
+fn click_tool_system(
+    interaction_query: Query<(&Interaction, &ToolIcon), Changed<Interaction>>,
+    mut select_tool: EventWriter<SelectTool>,
+) {
+    for (interaction, icon) in interaction_query.iter() {
+        if *interaction == Interaction::Clicked {
+            select_tool.send(SelectTool(icon.slot));
+        }
+    }
+}
+
+fn hotkey_tool_system(actions: ActionInput, mut select_tool: EventWriter<SelectTool>) {
+    for slot in 0..TOOL_HOTKEYS {
+        if actions.just_pressed(Action::SelectTool(slot)) {
+            select_tool.send(SelectTool(slot.into()));
+        }
+    }
+}
+
+/// Scrolling down selects the next tool and scrolling up the previous,
+/// wrapping around at the ends of the toolbar
+fn scroll_tool_system(
+    mut wheel_events: EventReader<MouseWheel>,
+    icon_query: Query<&ToolIcon>,
+    selected_tool: Res<Tool>,
+    mut select_tool: EventWriter<SelectTool>,
+) {
+    let steps: i32 = wheel_events
+        .iter()
+        .filter(|e| e.y != 0.0)
+        .map(|e| -e.y.signum() as i32)
+        .sum();
+    if steps == 0 {
+        return;
+    }
+    let slots = icon_query.iter().count() as i32;
+    if let Some(icon) = icon_query.iter().find(|icon| icon.tool == *selected_tool) {
+        let slot = (icon.slot as i32 + steps).rem_euclid(slots);
+        select_tool.send(SelectTool(slot as usize));
+    }
+}
+
+pub(crate) fn change_tool_system(
+    mut select_tool: EventReader<SelectTool>,
+    icon_query: Query<&ToolIcon>,
     mut selected_tool: ResMut<Tool>,
+) {
+    let Some(&SelectTool(slot)) = select_tool.iter().last() else {
+        return;
+    };
+    let Some(tool) = icon_query
+        .iter()
+        .find(|icon| icon.slot == slot)
+        .map(|icon| icon.tool)
+    else {
+        return;
+    };
+    *selected_tool = tool;
+}
+
+/// Shows the selected tool on the cursor, however it was selected
+fn cursor_icon_system(
+    selected_tool: Res<Tool>,
     mut cursor_query: Query<(&mut TextureAtlasSprite, &mut super::world::Cursor)>,
     tilemap: Res<Tilemap>,
 ) {
-    if let Some(new_tool) =
-        interaction_query
-            .iter()
-            .find_map(|(interaction, entity)| match interaction {
-                Interaction::Clicked => Some(entity),
-                _ => None,
-            })
-    {
-        for (_, mut color) in icon_query.iter_mut() {
-            color.0 = DESELECTED_COLOR;
+    if !selected_tool.is_changed() {
+        return;
+    }
+    let tool = *selected_tool;
+    let (mut cursor_sprite, mut cursor) = cursor_query.single_mut();
+    cursor_sprite.index = tool.icon(tilemap.textures());
+    cursor_sprite.custom_size = Some(tool.size().as_vec2());
+    cursor.offset = tool.cursor_offset();
+}
+
+/// Highlights the selected tool and greys out machines that have run out
+fn icon_color_system(
+    selected_tool: Res<Tool>,
+    budget: Res<Budget>,
+    added_query: Query<(), Added<ToolIcon>>,
+    mut icon_query: Query<(&ToolIcon, &mut UiColor)>,
+) {
+    if !selected_tool.is_changed() && !budget.is_changed() && added_query.is_empty() {
+        return;
+    }
+    for (icon, mut color) in icon_query.iter_mut() {
+        color.0 = match icon.tool {
+            Tool::Place(machine) if !budget.can_afford(machine) => EXHAUSTED_COLOR,
+            tool if tool == *selected_tool => SELECTED_COLOR,
+            _ => DESELECTED_COLOR,
+        };
+    }
+}
+
+fn budget_counter_system(
+    budget: Res<Budget>,
+    added_query: Query<(), Added<BudgetCounter>>,
+    mut counter_query: Query<(&BudgetCounter, &mut Text)>,
+) {
+    // The toolbar is spawned after the budget is set at the start of a game
+    if !budget.is_changed() && added_query.is_empty() {
+        return;
+    }
+    for (counter, mut text) in counter_query.iter_mut() {
+        text.sections[0].value = budget
+            .remaining(counter.0)
+            .map_or_else(String::new, |n| n.to_string());
+    }
+}
+
+fn tool_info(tool: Tool, hotkey: Option<Binding>) -> [String; 3] {
+    let size = tool.size();
+    let mut details = format!("\nSize: {}x{}", size.x, size.y);
+    if let Some(hotkey) = hotkey {
+        details += &format!("   Hotkey: {hotkey}");
+    }
+    [
+        tool.name().to_owned(),
+        details,
+        format!("\n{}", tool.description()),
+    ]
+}
+
+/// Shows the tooltip next to the mouse while it is over a tool icon
+fn tooltip_system(
+    windows: Res<Windows>,
+    icon_query: Query<(&Interaction, &ToolIcon)>,
+    mut tooltip_query: Query<(&mut Style, &Children), With<Tooltip>>,
+    mut text_query: Query<&mut Text>,
+    bindings: Res<Bindings>,
+    mut shown_slot: Local<Option<usize>>,
+) {
+    let (mut style, children) = tooltip_query.single_mut();
+    let hovered = icon_query
+        .iter()
+        .find(|(interaction, _)| **interaction != Interaction::None)
+        .map(|(_, icon)| icon);
+    let cursor = windows.get_primary().and_then(|w| w.cursor_position());
+
+    let (Some(icon), Some(cursor)) = (hovered, cursor) else {
+        style.display = Display::None;
+        *shown_slot = None;
+        return;
+    };
+
+    style.display = Display::Flex;
+    style.position.left = Val::Px(cursor.x + TOOLTIP_OFFSET.x);
+    style.position.bottom = Val::Px(cursor.y + TOOLTIP_OFFSET.y);
+    if *shown_slot != Some(icon.slot) {
+        *shown_slot = Some(icon.slot);
+        let hotkey = u8::try_from(icon.slot)
+            .ok()
+            .filter(|&slot| slot < TOOL_HOTKEYS)
+            .and_then(|slot| {
+                bindings
+                    .get(Action::SelectTool(slot))
+                    .into_iter()
+                    .flatten()
+                    .next()
+            });
+        let mut text = text_query.get_mut(children[0]).unwrap();
+        for (section, value) in text.sections.iter_mut().zip(tool_info(icon.tool, hotkey)) {
+            section.value = value;
         }
-        let (&ToolIcon { tool }, mut color) = icon_query.get_mut(new_tool).unwrap();
-        color.0 = SELECTED_COLOR;
-        *selected_tool = tool;
-        let (mut cursor_sprite, mut cursor) = cursor_query.single_mut();
-        cursor_sprite.index = tool.icon(tilemap.textures());
-        cursor_sprite.custom_size = Some(tool.size().as_vec2());
-        cursor.offset = tool.cursor_offset();
     }
 }
@@ -1,19 +1,19 @@
-use super::{Tool, ToolDirection};
+use super::{filter::SorterFilter, Budget, Tool, ToolDirection};
 use crate::{prelude::*, tilemap::*};
 use bevy::prelude::*;
 
 const CURSOR_COLOR_OK: Color = Color::rgba(1.0, 1.0, 1.0, 0.5);
+const CURSOR_COLOR_REFUSED: Color = Color::rgba(1.0, 0.3, 0.3, 0.5);
 
 pub struct Plugin;
 
 impl bevy::prelude::Plugin for Plugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(SystemSet::on_exit(AppState::LoadingAssets).with_system(setup_system))
+        app.add_system_set(SystemSet::on_enter(AppState::Game).with_system(setup_system))
             .add_system_set(
-                SystemSet::on_update(AppState::Game)
-                    .with_system(use_tool_system)
-                    .with_system(cursor_system),
-            );
+                SystemSet::on_exit(AppState::Game).with_system(despawn_all_system::<Cursor>),
+            )
+            .add_system_set(SystemSet::on_update(AppState::Game).with_system(cursor_system));
     }
 }
 
@@ -28,7 +28,7 @@ fn setup_system(mut commands: Commands, tilemap: Res<Tilemap>) {
     commands
         .spawn_bundle(SpriteSheetBundle {
             sprite: TextureAtlasSprite {
-                index: tilemap.textures().delete_tool.clone(),
+                index: tilemap.textures().delete_tool,
                 custom_size: Some(Vec2::ONE),
                 color: Color::NONE,
                 ..default()
@@ -43,19 +43,33 @@ fn setup_system(mut commands: Commands, tilemap: Res<Tilemap>) {
         });
 }
 
-fn use_tool_system(
+pub(crate) fn use_tool_system(
     mut commands: Commands,
     mouse_input: Res<MouseInput>,
     mut tilemap: ResMut<Tilemap>,
     tool: Res<Tool>,
     placing_direction: Res<ToolDirection>,
+    sorter_filter: Res<SorterFilter>,
+    mut budget: ResMut<Budget>,
 ) {
     if let Some(pos) = mouse_input.clicked_pos() {
-        match &*tool {
-            Tool::Delete => tilemap.remove(pos.tile, &mut commands),
-            Tool::Place(machine_type) => {
-                tilemap.try_add(pos.tile, *machine_type, placing_direction.0, &mut commands);
+        match (mouse_input.click, *tool) {
+            (Click::Remove, _) | (_, Tool::Delete) => {
+                if let Some(machine_type) = tilemap.remove(pos.tile, &mut commands) {
+                    budget.refund(machine_type);
+                }
             }
+            (_, Tool::Place(machine_type)) => {
+                if budget.can_afford(machine_type)
+                    && tilemap.try_add(pos.tile, machine_type, placing_direction.0, &mut commands)
+                {
+                    budget.spend(machine_type);
+                    if let Some(sorter) = tilemap.machine_mut::<Sorter>(pos.tile) {
+                        sorter.set_filter(sorter_filter.0.clone());
+                    }
+                }
+            }
+            (_, Tool::Inspect) => (),
         }
     }
 }
@@ -64,6 +78,9 @@ fn cursor_system(
     mut cursor_query: Query<(&mut Cursor, &mut Transform, &mut TextureAtlasSprite)>,
     placing_direction: Res<ToolDirection>,
     mouse_input: Res<MouseInput>,
+    tool: Res<Tool>,
+    tilemap: Res<Tilemap>,
+    budget: Res<Budget>,
     time: Res<Time>,
 ) {
     let (mut cursor, mut transform, mut sprite) = cursor_query.single_mut();
@@ -73,7 +90,19 @@ fn cursor_system(
 
     if let Some(ideal_position) = mouse_input.pos {
         cursor.target = ideal_position.tile;
-        sprite.color = CURSOR_COLOR_OK;
+        sprite.color = match *tool {
+            Tool::Place(machine)
+                if !budget.can_afford(machine)
+                    || !tilemap.in_build_area(
+                        ideal_position.tile,
+                        machine,
+                        placing_direction.0,
+                    ) =>
+            {
+                CURSOR_COLOR_REFUSED
+            }
+            _ => CURSOR_COLOR_OK,
+        };
         if !cursor.is_visible {
             cursor.is_visible = true;
             transform.translation = ideal_position.tile.as_vec2().extend(10.0)
@@ -0,0 +1,443 @@
+//! Records the player's input to a file and plays it back, so a game can be
+//! replayed exactly, with or without a window.
+//!
+//! Start the game with `--record <file>` to record every game played, each
+//! overwriting the last, or with `--replay <file>` to play a recording back.
+
+use crate::{
+    bindings::Bindings,
+    levels::Session,
+    placing::{filter, toolbar::change_tool_system, ToolDirection},
+    prelude::*,
+    rewind::{timeline, RewindTo},
+    simulation::{FixedTicks, SimClock},
+    tilemap::Layout,
+};
+use bevy::{
+    app::AppExit,
+    ecs::system::SystemParam,
+    input::{mouse::MouseWheel, InputSystem},
+    prelude::*,
+    ui::UiSystem,
+};
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    hash::Hash,
+    mem,
+    path::{Path, PathBuf},
+};
+
+pub use crate::placing::{filter::SetFilter, Tool};
+
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            if arg != "--record" && arg != "--replay" {
+                warn!("Ignoring unknown argument {arg}");
+                continue;
+            }
+            let Some(path) = args.next() else {
+                warn!("Ignoring {arg}, which needs a file after it");
+                continue;
+            };
+            if arg == "--record" {
+                app.insert_resource(Recorder::new(path.into()));
+                continue;
+            }
+            match Recording::load(path.as_ref()) {
+                Ok(recording) => {
+                    app.insert_resource(Playback::new(recording));
+                }
+                Err(e) => error!("Failed to load recording from {path}: {e}"),
+            }
+        }
+
+        app.add_system_set_to_stage(
+            CoreStage::PreUpdate,
+            playback_systems()
+                .after(InputSystem)
+                .after(crate::capture_mouse_input_system)
+                .before(UiSystem::Focus),
+        )
+        .add_system_to_stage(
+            CoreStage::PreUpdate,
+            block_interaction_system.after(UiSystem::Focus),
+        )
+        .add_system_to_stage(CoreStage::Last, save_on_exit_system)
+        .add_system_set(SystemSet::on_update(AppState::MainMenu).with_system(start_playback_system))
+        .add_system_set(
+            SystemSet::on_enter(AppState::Game)
+                .with_system(begin_playback_system)
+                .with_system(start_recording_system),
+        )
+        .add_system_set(
+            SystemSet::on_update(AppState::Game).with_system(
+                record_system
+                    .after(change_tool_system)
+                    .after(timeline::click_system)
+                    .after(filter::click_system),
+            ),
+        )
+        .add_system_set(SystemSet::on_exit(AppState::Game).with_system(save_recording_system));
+    }
+}
+
+/// Everything the player did in one game, frame by frame
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Recording {
+    /// Index of the level played, or `None` for sandbox mode
+    pub level: Option<usize>,
+    /// The layout the game started with
+    pub layout: Layout,
+    /// The direction machines were placed in at the start of the game
+    pub direction: Side,
+    /// The bindings the recording was made with, used instead of the
+    /// player's while it plays back
+    pub bindings: Bindings,
+    pub frames: Vec<Frame>,
+}
+
+impl Recording {
+    /// Reads a recording saved by [`Recording::save`]
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        ron::from_str(&source).map_err(|e| e.to_string())
+    }
+
+    /// Saves the recording as RON, with one frame on each line
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let source = ron::ser::to_string_pretty(self, PrettyConfig::new().depth_limit(2))
+            .map_err(|e| e.to_string())?;
+        fs::write(path, source).map_err(|e| e.to_string())
+    }
+}
+
+/// The input for one frame of a game
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Frame {
+    /// How many simulation ticks were run
+    pub ticks: u32,
+    pub mouse: MouseInput,
+    pub keys: Buttons<KeyCode>,
+    pub mouse_buttons: Buttons<MouseButton>,
+    /// The tool used this frame, after any hotkeys or clicks on the toolbar
+    pub tool: Tool,
+    /// The snapshot rewound to from the timeline, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rewind: Option<usize>,
+    /// Sorter filters picked this frame
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filters: Vec<SetFilter>,
+}
+
+/// The state of a set of buttons for one frame
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(deserialize = "T: Deserialize<'de>"))]
+pub struct Buttons<T> {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pressed: Vec<T>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub just_pressed: Vec<T>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub just_released: Vec<T>,
+}
+
+impl<T> Default for Buttons<T> {
+    fn default() -> Self {
+        Buttons {
+            pressed: Vec::new(),
+            just_pressed: Vec::new(),
+            just_released: Vec::new(),
+        }
+    }
+}
+
+impl<T: Copy + Eq + Hash + Send + Sync + 'static> Buttons<T> {
+    fn capture(input: &Input<T>) -> Self {
+        Buttons {
+            pressed: input.get_pressed().copied().collect(),
+            just_pressed: input.get_just_pressed().copied().collect(),
+            just_released: input.get_just_released().copied().collect(),
+        }
+    }
+
+    /// Replaces the state of `input` with this
+    fn apply(&self, input: &mut Input<T>) {
+        input.reset_all();
+        for &button in self.pressed.iter() {
+            input.press(button);
+            if !self.just_pressed.contains(&button) {
+                input.clear_just_pressed(button);
+            }
+        }
+        // Buttons can be pressed and released within the same frame
+        for &button in self.just_pressed.iter() {
+            input.press(button);
+        }
+        for &button in self.just_released.iter() {
+            input.press(button);
+            input.release(button);
+            if !self.just_pressed.contains(&button) {
+                input.clear_just_pressed(button);
+            }
+        }
+    }
+}
+
+/// Records each game to a file, given by `--record`
+#[derive(Debug)]
+pub struct Recorder {
+    path: PathBuf,
+    recording: Recording,
+}
+
+impl Recorder {
+    #[must_use]
+    pub fn new(path: PathBuf) -> Self {
+        Recorder {
+            path,
+            recording: default(),
+        }
+    }
+
+    fn save(&self) {
+        match self.recording.save(&self.path) {
+            Ok(()) => info!(
+                "Saved {} frames of input to {}",
+                self.recording.frames.len(),
+                self.path.display()
+            ),
+            Err(e) => error!("Failed to save recording to {}: {e}", self.path.display()),
+        }
+    }
+}
+
+/// Feeds a recording to the game in place of the player's input, until it
+/// runs out. The real mouse can't interact with the UI meanwhile, as clicks
+/// on it are played back from the recording
+#[derive(Debug)]
+pub struct Playback {
+    recording: Recording,
+    /// Set when the recorded game has been started
+    started: bool,
+    /// The next frame to play, once the game has been entered
+    next_frame: Option<usize>,
+    /// The frame being played this frame
+    current_frame: Option<usize>,
+    finished: bool,
+    /// The player's bindings, put back once the recording finishes
+    player_bindings: Option<Bindings>,
+}
+
+impl Playback {
+    #[must_use]
+    pub fn new(recording: Recording) -> Self {
+        Playback {
+            recording,
+            started: false,
+            next_frame: None,
+            current_frame: None,
+            finished: false,
+            player_bindings: None,
+        }
+    }
+
+    /// Returns true once every frame has been played, which is a frame
+    /// before the player gets control back
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.finished || self.next_frame == Some(self.recording.frames.len())
+    }
+
+    fn current(&self) -> Option<&Frame> {
+        self.current_frame
+            .map(|index| &self.recording.frames[index])
+    }
+}
+
+/// Starts the recorded game as soon as the main menu is reached
+fn start_playback_system(
+    mut commands: Commands,
+    playback: Option<ResMut<Playback>>,
+    mut state: ResMut<State<AppState>>,
+) {
+    let Some(mut playback) = playback else {
+        return;
+    };
+    if playback.started {
+        return;
+    }
+    playback.started = true;
+    commands.insert_resource(Session {
+        level: playback.recording.level,
+        layout: playback.recording.layout.clone(),
+    });
+    state.set(AppState::Game).unwrap();
+}
+
+/// Plays from the first frame after the game is entered, as that's the
+/// first frame that gets recorded
+pub(crate) fn begin_playback_system(playback: Option<ResMut<Playback>>) {
+    if let Some(mut playback) = playback {
+        if !playback.finished {
+            playback.next_frame = Some(0);
+        }
+    }
+}
+
+/// Systems that play back the current frame in [`CoreStage::PreUpdate`],
+/// taking the place of the player's input
+pub(crate) fn playback_systems() -> SystemSet {
+    SystemSet::new()
+        .with_system(playback_system)
+        .with_system(playback_input_system.after(playback_system))
+}
+
+/// Moves on to the next frame, setting up everything that isn't input
+fn playback_system(
+    mut commands: Commands,
+    playback: Option<ResMut<Playback>>,
+    mut tool: ResMut<Tool>,
+    mut direction: ResMut<ToolDirection>,
+    mut bindings: ResMut<Bindings>,
+    mut rewinds: EventWriter<RewindTo>,
+    mut filters: EventWriter<SetFilter>,
+) {
+    let Some(mut playback) = playback else {
+        return;
+    };
+    let Some(index) = playback.next_frame else {
+        return;
+    };
+    if index == 0 {
+        let recorded = playback.recording.bindings.clone();
+        playback.player_bindings = Some(mem::replace(&mut *bindings, recorded));
+        direction.0 = playback.recording.direction;
+    }
+    if index >= playback.recording.frames.len() {
+        if let Some(player_bindings) = playback.player_bindings.take() {
+            *bindings = player_bindings;
+        }
+        playback.next_frame = None;
+        playback.current_frame = None;
+        playback.finished = true;
+        commands.remove_resource::<FixedTicks>();
+        info!("Finished playing back {index} frames");
+        return;
+    }
+    playback.next_frame = Some(index + 1);
+    playback.current_frame = Some(index);
+    let frame = &playback.recording.frames[index];
+    commands.insert_resource(FixedTicks(frame.ticks));
+    if *tool != frame.tool {
+        *tool = frame.tool;
+    }
+    if let Some(index) = frame.rewind {
+        rewinds.send(RewindTo(index));
+    }
+    filters.send_batch(frame.filters.iter().cloned());
+}
+
+/// Stops the real mouse hovering over or clicking the UI while a recording
+/// plays, so the game only sees what was recorded
+fn block_interaction_system(
+    playback: Option<Res<Playback>>,
+    mut interaction_query: Query<&mut Interaction>,
+) {
+    if playback.is_none_or(|playback| playback.current().is_none()) {
+        return;
+    }
+    for mut interaction in interaction_query.iter_mut() {
+        if *interaction != Interaction::None {
+            *interaction = Interaction::None;
+        }
+    }
+}
+
+/// Replaces the player's input with the current frame's
+fn playback_input_system(
+    playback: Option<Res<Playback>>,
+    mut keys: ResMut<Input<KeyCode>>,
+    mut mouse_buttons: ResMut<Input<MouseButton>>,
+    mut mouse_input: ResMut<MouseInput>,
+    wheel_events: Option<ResMut<Events<MouseWheel>>>,
+) {
+    let Some(frame) = playback.as_ref().and_then(|p| p.current()) else {
+        return;
+    };
+    frame.keys.apply(&mut keys);
+    frame.mouse_buttons.apply(&mut mouse_buttons);
+    *mouse_input = frame.mouse.clone();
+    if let Some(mut wheel_events) = wheel_events {
+        wheel_events.clear();
+    }
+}
+
+fn start_recording_system(
+    recorder: Option<ResMut<Recorder>>,
+    session: Res<Session>,
+    direction: Res<ToolDirection>,
+    bindings: Res<Bindings>,
+) {
+    if let Some(mut recorder) = recorder {
+        recorder.recording = Recording {
+            level: session.level,
+            layout: session.layout.clone(),
+            direction: direction.0,
+            bindings: bindings.clone(),
+            frames: Vec::new(),
+        };
+    }
+}
+
+/// Clicks on the UI that change the game, which are recorded as events
+/// rather than input because the UI isn't played back
+#[derive(SystemParam)]
+struct UiEvents<'w, 's> {
+    rewinds: EventReader<'w, 's, RewindTo>,
+    filters: EventReader<'w, 's, SetFilter>,
+}
+
+fn record_system(
+    recorder: Option<ResMut<Recorder>>,
+    clock: Res<SimClock>,
+    mouse_input: Res<MouseInput>,
+    keys: Res<Input<KeyCode>>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    tool: Res<Tool>,
+    mut ui_events: UiEvents,
+) {
+    if let Some(mut recorder) = recorder {
+        recorder.recording.frames.push(Frame {
+            ticks: clock.frame_ticks(),
+            mouse: mouse_input.clone(),
+            keys: Buttons::capture(&keys),
+            mouse_buttons: Buttons::capture(&mouse_buttons),
+            tool: *tool,
+            rewind: ui_events.rewinds.iter().last().map(|rewind| rewind.0),
+            filters: ui_events.filters.iter().cloned().collect(),
+        });
+    }
+}
+
+fn save_recording_system(recorder: Option<Res<Recorder>>) {
+    if let Some(recorder) = recorder {
+        recorder.save();
+    }
+}
+
+/// Saves the game being played if the app is closed during it
+fn save_on_exit_system(
+    recorder: Option<Res<Recorder>>,
+    state: Res<State<AppState>>,
+    mut app_exit: EventReader<AppExit>,
+) {
+    if app_exit.iter().next().is_some() && *state.current() == AppState::Game {
+        save_recording_system(recorder);
+    }
+}
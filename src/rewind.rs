@@ -0,0 +1,138 @@
+//! Snapshots of the running simulation, so it can be rewound a few seconds
+//! without changing the layout
+
+use crate::{
+    items::{spawn_item, Item, Momentum},
+    prelude::*,
+    scores::GoalProgress,
+    simulation::SimClock,
+    tilemap::{HeldItems, Tilemap},
+};
+use bevy::{math::Vec3Swizzles, prelude::*};
+use std::collections::VecDeque;
+
+pub(crate) mod timeline;
+
+/// How many ticks apart snapshots are taken
+pub const SNAPSHOT_INTERVAL: u64 = 30;
+/// How many snapshots are kept, which is how far back the simulation can be
+/// rewound
+pub const SNAPSHOT_COUNT: usize = 40;
+
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(HeadlessPlugin).add_plugin(timeline::Plugin);
+    }
+}
+
+/// Takes snapshots and rewinds to them, without the timeline
+pub(crate) struct HeadlessPlugin;
+
+impl bevy::prelude::Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<RewindTo>()
+            .init_resource::<Snapshots>()
+            .add_system_set(SystemSet::on_enter(AppState::Game).with_system(reset_system))
+            .add_system_set(
+                SystemSet::on_update(AppState::Game)
+                    .with_system(rewind_system)
+                    .with_system(snapshot_system.after(rewind_system)),
+            );
+    }
+}
+
+/// Everything the simulation changes, as it was at the end of a tick
+#[derive(Debug, Clone)]
+struct Snapshot {
+    tick: u64,
+    /// Items that aren't on belts, with their positions and momentum
+    loose_items: Vec<(Item, Vec2, Vec2)>,
+    held_items: HeldItems,
+    progress: GoalProgress,
+}
+
+/// The last [`SNAPSHOT_COUNT`] snapshots of the current game, oldest first
+#[derive(Debug, Default)]
+pub(crate) struct Snapshots(VecDeque<Snapshot>);
+
+impl Snapshots {
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// The tick the snapshot at `index` was taken on
+    #[must_use]
+    pub fn tick(&self, index: usize) -> Option<u64> {
+        self.0.get(index).map(|snapshot| snapshot.tick)
+    }
+}
+
+/// Puts the simulation back to the snapshot at this index in [`Snapshots`],
+/// forgetting every newer snapshot
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RewindTo(pub usize);
+
+fn reset_system(mut snapshots: ResMut<Snapshots>) {
+    snapshots.0.clear();
+}
+
+fn snapshot_system(
+    mut snapshots: ResMut<Snapshots>,
+    clock: Res<SimClock>,
+    tilemap: Res<Tilemap>,
+    progress: Res<GoalProgress>,
+    items_query: Query<(&Item, &Transform, &Momentum)>,
+) {
+    let is_due = snapshots.0.back().map_or(clock.tick() > 0, |last| {
+        clock.tick() >= last.tick + SNAPSHOT_INTERVAL
+    });
+    if !is_due {
+        return;
+    }
+    snapshots.0.push_back(Snapshot {
+        tick: clock.tick(),
+        loose_items: items_query
+            .iter()
+            .map(|(item, transform, momentum)| {
+                (item.clone(), transform.translation.xy(), momentum.0)
+            })
+            .collect(),
+        held_items: tilemap.held_items(),
+        progress: progress.clone(),
+    });
+    if snapshots.0.len() > SNAPSHOT_COUNT {
+        snapshots.0.pop_front();
+    }
+}
+
+fn rewind_system(
+    mut commands: Commands,
+    mut rewinds: EventReader<RewindTo>,
+    mut snapshots: ResMut<Snapshots>,
+    mut tilemap: ResMut<Tilemap>,
+    mut clock: ResMut<SimClock>,
+    mut progress: ResMut<GoalProgress>,
+    items_query: Query<Entity, With<Item>>,
+) {
+    let Some(&RewindTo(index)) = rewinds.iter().last() else {
+        return;
+    };
+    if index >= snapshots.len() {
+        return;
+    }
+    snapshots.0.truncate(index + 1);
+    let snapshot = snapshots.0.back().unwrap();
+
+    for entity in items_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    for (item, pos, momentum) in snapshot.loose_items.iter() {
+        spawn_item(&mut commands, item.clone(), *pos, *momentum);
+    }
+    tilemap.restore_held_items(&snapshot.held_items, &mut commands);
+    clock.rewind(snapshot.tick);
+    *progress = snapshot.progress.clone();
+}
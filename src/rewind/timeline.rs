@@ -0,0 +1,158 @@
+use super::{RewindTo, Snapshots, SNAPSHOT_COUNT};
+use crate::{
+    prelude::*,
+    simulation::{SimClock, TICKS_PER_SECOND},
+    ui::{BlocksWorldInput, UiFont, TEXT_COLOR},
+};
+use bevy::prelude::*;
+
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(AppState::Game).with_system(setup_system))
+            .add_system_set(
+                SystemSet::on_update(AppState::Game)
+                    .with_system(click_system)
+                    .with_system(slot_color_system)
+                    .with_system(label_system),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::Game).with_system(despawn_all_system::<Timeline>),
+            );
+    }
+}
+
+#[derive(Component)]
+struct Timeline;
+
+/// One place on the timeline, oldest first, with the newest snapshot always
+/// in the last slot
+#[derive(Component)]
+pub(crate) struct Slot(usize);
+
+/// Says how far back the snapshot under the mouse is
+#[derive(Component)]
+struct TimelineLabel;
+
+const PANEL_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.75);
+const EMPTY_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.1);
+const FILLED_COLOR: Color = Color::rgb(0.4, 0.4, 0.4);
+const HOVERED_COLOR: Color = Color::WHITE;
+const SLOT_SIZE: Vec2 = Vec2::new(6.0, 18.0);
+const LABEL_WIDTH: f32 = 64.0;
+
+impl Slot {
+    /// Index of the snapshot in this slot, if there is one yet
+    fn snapshot(&self, snapshots: &Snapshots) -> Option<usize> {
+        (self.0 + snapshots.len()).checked_sub(SNAPSHOT_COUNT)
+    }
+}
+
+fn setup_system(mut commands: Commands, font: Res<UiFont>) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Px(0.0),
+                    right: Val::Px(0.0),
+                    top: Val::Px(0.0),
+                    ..default()
+                },
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .insert(Timeline)
+        .with_children(|root| {
+            root.spawn_bundle(NodeBundle {
+                style: Style {
+                    align_items: AlignItems::Center,
+                    padding: UiRect::all(Val::Px(4.0)),
+                    ..default()
+                },
+                color: PANEL_COLOR.into(),
+                ..default()
+            })
+            .insert(BlocksWorldInput)
+            .with_children(|bar| {
+                bar.spawn_bundle(TextBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(LABEL_WIDTH), Val::Auto),
+                        ..default()
+                    },
+                    ..font.text("Rewind", 16.0, TEXT_COLOR)
+                })
+                .insert(TimelineLabel);
+                for slot in 0..SNAPSHOT_COUNT {
+                    bar.spawn_bundle(ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(SLOT_SIZE.x), Val::Px(SLOT_SIZE.y)),
+                            margin: UiRect::all(Val::Px(1.0)),
+                            ..default()
+                        },
+                        color: EMPTY_COLOR.into(),
+                        ..default()
+                    })
+                    .insert(Slot(slot));
+                }
+            });
+        });
+}
+
+/// Clicking a slot rewinds to its snapshot
+pub(crate) fn click_system(
+    slot_query: Query<(&Interaction, &Slot), Changed<Interaction>>,
+    snapshots: Res<Snapshots>,
+    mut rewinds: EventWriter<RewindTo>,
+) {
+    for (interaction, slot) in slot_query.iter() {
+        if *interaction == Interaction::Clicked {
+            if let Some(index) = slot.snapshot(&snapshots) {
+                rewinds.send(RewindTo(index));
+            }
+        }
+    }
+}
+
+fn slot_color_system(
+    snapshots: Res<Snapshots>,
+    mut slot_query: Query<(&Interaction, &Slot, &mut UiColor)>,
+) {
+    for (interaction, slot, mut color) in slot_query.iter_mut() {
+        let new_color = match (slot.snapshot(&snapshots), interaction) {
+            (None, _) => EMPTY_COLOR,
+            (Some(_), Interaction::None) => FILLED_COLOR,
+            (Some(_), _) => HOVERED_COLOR,
+        };
+        if color.0 != new_color {
+            color.0 = new_color;
+        }
+    }
+}
+
+fn label_system(
+    snapshots: Res<Snapshots>,
+    clock: Res<SimClock>,
+    slot_query: Query<(&Interaction, &Slot)>,
+    mut label_query: Query<&mut Text, With<TimelineLabel>>,
+) {
+    let hovered_tick = slot_query
+        .iter()
+        .filter(|(interaction, _)| **interaction != Interaction::None)
+        .find_map(|(_, slot)| snapshots.tick(slot.snapshot(&snapshots)?));
+    let label = hovered_tick.map_or_else(
+        || "Rewind".to_owned(),
+        |tick| {
+            let seconds = clock.tick().saturating_sub(tick) as f32 / TICKS_PER_SECOND as f32;
+            format!("-{seconds:.1}s")
+        },
+    );
+    let mut text = label_query.single_mut();
+    if text.sections[0].value != label {
+        text.sections[0].value = label;
+    }
+}
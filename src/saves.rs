@@ -0,0 +1,417 @@
+//! Saving the game being played to the data directory, when the player asks
+//! and automatically in the background
+
+use crate::{
+    bindings::{Action, ActionInput},
+    levels::{Levels, Session},
+    prelude::*,
+    replay::Playback,
+    tilemap::{save_session_system, Layout, Tilemap},
+    ui::{UiFont, DISABLED_TEXT_COLOR},
+};
+use bevy::{app::AppExit, prelude::*, tasks::IoTaskPool};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const SAVE_FILE_NAME: &str = "save.ron";
+const AUTOSAVE_DIR_NAME: &str = "autosaves";
+/// Exists while the game is running, so finding it on startup means the last
+/// session didn't exit cleanly
+const RUNNING_FILE_NAME: &str = "running";
+/// Which autosave the player last chose not to recover
+const DISMISSED_FILE_NAME: &str = "dismissed.ron";
+/// How often the game is autosaved while playing
+pub const AUTOSAVE_MINUTES: f32 = 2.0;
+/// How many autosaves are kept, deleting the oldest
+pub const AUTOSAVE_COUNT: usize = 5;
+/// How long "Game saved" is shown for
+const NOTICE_SECONDS: f32 = 2.0;
+
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        let levels = app.world.resource::<Levels>();
+        let save = save_path().and_then(|path| load_game(&path, levels));
+        let saved_at = save.as_ref().map_or(0, |save| save.saved_at);
+        let crashed = start_session();
+        let dismissed = dismissed_path().and_then(|path| load_ron::<u64>(&path));
+        let newest = newest_autosave()
+            .filter(|(stamp, _)| dismissed.is_none_or(|dismissed| *stamp > dismissed))
+            .and_then(|(stamp, path)| Some((stamp, load_game(&path, levels)?)))
+            .filter(|(_, autosave)| autosave.saved_at > saved_at);
+        match (newest, save) {
+            // The player is asked before trusting what a crashed session left
+            (Some((stamp, autosave)), save) if crashed => {
+                if let Some(save) = save {
+                    app.insert_resource(save.session());
+                }
+                app.insert_resource(Recovery {
+                    autosave,
+                    stamp,
+                    last_save: saved_at,
+                });
+            }
+            // Every session autosaves as it's left, so after a clean exit the
+            // newest autosave is where the player left off
+            (Some((_, game)), _) | (None, Some(game)) => {
+                app.insert_resource(game.session());
+            }
+            (None, None) => {}
+        }
+
+        app.insert_resource(AutosaveTimer(Timer::from_seconds(
+            AUTOSAVE_MINUTES * 60.0,
+            true,
+        )))
+        .add_system_set(SystemSet::on_update(AppState::MainMenu).with_system(recovery_system))
+        .add_system_set(SystemSet::on_enter(AppState::Game).with_system(setup_system))
+        .add_system_set(
+            SystemSet::on_update(AppState::Game)
+                .with_system(save_system)
+                .with_system(autosave_system)
+                .with_system(notice_system),
+        )
+        .add_system_set(
+            SystemSet::on_exit(AppState::Game)
+                .with_system(autosave_on_exit_system.after(save_session_system))
+                .with_system(despawn_all_system::<SaveNotice>),
+        )
+        .add_system_to_stage(CoreStage::Last, autosave_on_app_exit_system)
+        .add_system_to_stage(
+            CoreStage::Last,
+            end_session_system.after(autosave_on_app_exit_system),
+        );
+    }
+}
+
+/// A game saved to disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedGame {
+    /// When the game was saved, in seconds since the Unix epoch
+    pub saved_at: u64,
+    /// Index of the level being played, or `None` for sandbox mode
+    pub level: Option<usize>,
+    pub layout: Layout,
+}
+
+impl SavedGame {
+    fn new(level: Option<usize>, layout: Layout) -> Self {
+        SavedGame {
+            saved_at: now(),
+            level,
+            layout,
+        }
+    }
+
+    #[must_use]
+    pub fn session(&self) -> Session {
+        Session {
+            level: self.level,
+            layout: self.layout.clone(),
+        }
+    }
+}
+
+/// An autosave newer than the last save, left by a session that didn't exit
+/// cleanly, which the player is asked about on startup
+#[derive(Debug)]
+pub(crate) struct Recovery {
+    pub autosave: SavedGame,
+    /// Identifies the autosave's file
+    stamp: u64,
+    /// When the game was last saved by the player, or 0 if it never was
+    pub last_save: u64,
+}
+
+impl Recovery {
+    /// Remembers that the player chose not to recover the autosave, so they
+    /// aren't asked about it again. The autosave itself is kept
+    pub fn discard(&self) {
+        let Some(path) = dismissed_path() else {
+            return;
+        };
+        if let Err(e) = write_ron(&path, &self.stamp) {
+            error!("Failed to write {}: {e}", path.display());
+        }
+    }
+}
+
+#[derive(Debug)]
+struct AutosaveTimer(Timer);
+
+/// Briefly shows that the game was saved
+#[derive(Component)]
+struct SaveNotice(Timer);
+
+pub(crate) fn data_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("multifactory"))
+}
+
+/// Writes `value` to `path` as RON, creating its directory if needed
+pub(crate) fn write_ron(path: &Path, value: &impl Serialize) -> Result<(), String> {
+    let source = ron::ser::to_string_pretty(value, default()).map_err(|e| e.to_string())?;
+    fs::create_dir_all(path.parent().unwrap()).map_err(|e| e.to_string())?;
+    fs::write(path, source).map_err(|e| e.to_string())
+}
+
+/// Loads a RON file, logging why it couldn't be if it exists
+fn load_ron<T: for<'de> Deserialize<'de>>(path: &Path) -> Option<T> {
+    let source = fs::read_to_string(path).ok()?;
+    ron::from_str(&source)
+        .map_err(|e| warn!("Ignoring invalid {}: {e}", path.display()))
+        .ok()
+}
+
+fn save_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join(SAVE_FILE_NAME))
+}
+
+fn autosave_dir() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join(AUTOSAVE_DIR_NAME))
+}
+
+fn running_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join(RUNNING_FILE_NAME))
+}
+
+fn dismissed_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join(DISMISSED_FILE_NAME))
+}
+
+/// Loads a saved game, ignoring it if its level doesn't exist
+fn load_game(path: &Path, levels: &Levels) -> Option<SavedGame> {
+    let game = load_ron::<SavedGame>(path)?;
+    if game.level.is_some_and(|level| levels.get(level).is_none()) {
+        warn!(
+            "Ignoring {}, which is for a level that doesn't exist",
+            path.display()
+        );
+        return None;
+    }
+    Some(game)
+}
+
+/// Marks the game as running, returning whether the last session was still
+/// marked as running, meaning it crashed or was killed
+fn start_session() -> bool {
+    let Some(path) = running_path() else {
+        return false;
+    };
+    let crashed = path.exists();
+    if let Err(e) = fs::create_dir_all(path.parent().unwrap()).and_then(|()| fs::write(&path, "")) {
+        error!("Failed to write {}: {e}", path.display());
+    }
+    crashed
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_secs()
+}
+
+/// A number for naming an autosave's file, which is the time in milliseconds
+/// but always greater than the last one, so autosaves never share a file and
+/// sort in the order they were made
+fn autosave_stamp() -> u64 {
+    static LAST: AtomicU64 = AtomicU64::new(0);
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_millis() as u64;
+    let next = |last: u64| millis.max(last + 1);
+    let last = LAST.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |last| {
+        Some(next(last))
+    });
+    next(last.unwrap())
+}
+
+/// Every autosave file and its stamp, oldest first
+fn autosaves() -> Vec<(u64, PathBuf)> {
+    let Some(Ok(entries)) = autosave_dir().map(fs::read_dir) else {
+        return Vec::new();
+    };
+    let mut autosaves: Vec<_> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let saved_at = path
+                .file_name()?
+                .to_str()?
+                .strip_prefix("autosave-")?
+                .strip_suffix(".ron")?
+                .parse()
+                .ok()?;
+            Some((saved_at, path))
+        })
+        .collect();
+    autosaves.sort();
+    autosaves
+}
+
+fn newest_autosave() -> Option<(u64, PathBuf)> {
+    autosaves().pop()
+}
+
+/// Writes an autosave and deletes the oldest ones, without blocking the game
+fn autosave(game: SavedGame) {
+    let stamp = autosave_stamp();
+    IoTaskPool::get()
+        .spawn(async move { write_autosave(stamp, &game) })
+        .detach();
+}
+
+/// Writes an autosave and deletes the oldest ones
+fn write_autosave(stamp: u64, game: &SavedGame) {
+    let Some(dir) = autosave_dir() else {
+        return;
+    };
+    let path = dir.join(format!("autosave-{stamp}.ron"));
+    if let Err(e) = write_ron(&path, game) {
+        error!("Failed to autosave to {}: {e}", path.display());
+        return;
+    }
+    let autosaves = autosaves();
+    let old = autosaves.len().saturating_sub(AUTOSAVE_COUNT);
+    for (_, path) in autosaves.into_iter().take(old) {
+        // Another autosave's task may have deleted it already
+        match fs::remove_file(&path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => {
+                error!("Failed to delete old autosave {}: {e}", path.display());
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Describes how long ago something was saved
+#[must_use]
+pub fn describe_age(saved_at: u64) -> String {
+    let minutes = now().saturating_sub(saved_at) / 60;
+    match minutes {
+        0 => "just now".to_owned(),
+        1 => "1 minute ago".to_owned(),
+        2..=119 => format!("{minutes} minutes ago"),
+        120..=2879 => format!("{} hours ago", minutes / 60),
+        _ => format!("{} days ago", minutes / 1440),
+    }
+}
+
+/// Asks about recovering an autosave once the main menu is first reached,
+/// unless a recording is about to be played
+fn recovery_system(
+    recovery: Option<Res<Recovery>>,
+    playback: Option<Res<Playback>>,
+    mut state: ResMut<State<AppState>>,
+) {
+    if recovery.is_some() && playback.is_none() {
+        state.set(AppState::Recover).unwrap();
+    }
+}
+
+fn setup_system(mut commands: Commands, font: Res<UiFont>, mut timer: ResMut<AutosaveTimer>) {
+    timer.0.reset();
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Px(8.0),
+                    bottom: Val::Px(100.0),
+                    ..default()
+                },
+                ..default()
+            },
+            ..font.text("", 16.0, DISABLED_TEXT_COLOR)
+        })
+        .insert(SaveNotice(Timer::from_seconds(NOTICE_SECONDS, false)));
+}
+
+fn save_system(
+    actions: ActionInput,
+    tilemap: Res<Tilemap>,
+    session: Res<Session>,
+    mut notice_query: Query<(&mut Text, &mut SaveNotice)>,
+) {
+    if !actions.just_pressed(Action::Save) {
+        return;
+    }
+    let game = SavedGame::new(session.level, tilemap.layout());
+    let message = match save_path() {
+        Some(path) => match write_ron(&path, &game) {
+            Ok(()) => "Game saved",
+            Err(e) => {
+                error!("Failed to save to {}: {e}", path.display());
+                "Failed to save"
+            }
+        },
+        None => {
+            warn!("No data directory, the game will not be saved");
+            "Failed to save"
+        }
+    };
+    let (mut text, mut notice) = notice_query.single_mut();
+    text.sections[0].value = message.to_owned();
+    notice.0.reset();
+}
+
+fn autosave_system(
+    time: Res<Time>,
+    mut timer: ResMut<AutosaveTimer>,
+    tilemap: Res<Tilemap>,
+    session: Res<Session>,
+) {
+    if timer.0.tick(time.delta()).just_finished() {
+        autosave(SavedGame::new(session.level, tilemap.layout()));
+    }
+}
+
+/// Clears the running marker when the game is quit, so the next startup knows
+/// it exited cleanly
+fn end_session_system(mut app_exit: EventReader<AppExit>) {
+    if app_exit.iter().last().is_none() {
+        return;
+    }
+    if let Some(path) = running_path() {
+        if let Err(e) = fs::remove_file(&path) {
+            error!("Failed to delete {}: {e}", path.display());
+        }
+    }
+}
+
+/// Autosaves the game being played if the app is closed during it, which
+/// doesn't leave `AppState::Game`. The autosave is written before the app
+/// exits rather than in the background
+fn autosave_on_app_exit_system(
+    state: Res<State<AppState>>,
+    tilemap: Option<Res<Tilemap>>,
+    session: Option<Res<Session>>,
+    mut app_exit: EventReader<AppExit>,
+) {
+    if app_exit.iter().next().is_none() || *state.current() != AppState::Game {
+        return;
+    }
+    if let (Some(tilemap), Some(session)) = (tilemap, session) {
+        let game = SavedGame::new(session.level, tilemap.layout());
+        write_autosave(autosave_stamp(), &game);
+    }
+}
+
+/// Autosaves the layout the session was left with
+fn autosave_on_exit_system(session: Res<Session>) {
+    autosave(SavedGame::new(session.level, session.layout.clone()));
+}
+
+fn notice_system(time: Res<Time>, mut notice_query: Query<(&mut Text, &mut SaveNotice)>) {
+    let (mut text, mut notice) = notice_query.single_mut();
+    if notice.0.tick(time.delta()).just_finished() {
+        text.sections[0].value.clear();
+    }
+}
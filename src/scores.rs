@@ -0,0 +1,324 @@
+use crate::{
+    items::{item_momentum_system, lane_system},
+    levels::{Goal, Levels, Session},
+    prelude::*,
+    saves::{data_dir, write_ron},
+    simulation::{SimClock, SimulationStage, TICKS_PER_SECOND},
+    stats::{ItemEvent, ItemEventKind},
+    tilemap::{Layout, Tilemap},
+    ui::{UiFont, TEXT_COLOR},
+};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fs,
+    path::PathBuf,
+};
+
+const BESTS_FILE_NAME: &str = "bests.ron";
+/// Directory the layout of each completed level is saved to
+const SOLUTIONS_DIR_NAME: &str = "solutions";
+/// Throughput is measured over this many seconds before the goal was met
+pub const THROUGHPUT_SECONDS: u64 = 10;
+
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(HeadlessPlugin)
+            .insert_resource(PersonalBests::load())
+            .add_system_set(SystemSet::on_enter(AppState::Game).with_system(setup_system))
+            .add_system_set(
+                SystemSet::on_update(AppState::Game)
+                    .with_system(goal_text_system)
+                    .with_system(results_system),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::Game).with_system(despawn_all_system::<GoalText>),
+            );
+    }
+}
+
+/// How good a solution to a level is
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Score {
+    /// How many machines were placed
+    pub machines: u32,
+    /// How many tiles the machines cover
+    pub tiles: u32,
+    /// How long it took to meet the goal
+    pub ticks: u64,
+    /// Goal items delivered per minute, between the first and last
+    /// deliveries in the last [`THROUGHPUT_SECONDS`] before the goal was met
+    pub throughput: f32,
+}
+
+impl Score {
+    /// The best of each part of two scores, which may come from different
+    /// solutions
+    #[must_use]
+    pub fn best(self, other: Score) -> Score {
+        Score {
+            machines: self.machines.min(other.machines),
+            tiles: self.tiles.min(other.tiles),
+            ticks: self.ticks.min(other.ticks),
+            throughput: self.throughput.max(other.throughput),
+        }
+    }
+}
+
+/// Tracks progress towards the goal, without showing it or recording scores
+pub(crate) struct HeadlessPlugin;
+
+impl bevy::prelude::Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GoalProgress>()
+            .add_system_set(SystemSet::on_enter(AppState::Game).with_system(reset_progress_system))
+            .add_system_set_to_stage(SimulationStage, simulation_systems());
+    }
+}
+
+/// Systems that track the goal, which run every tick in [`SimulationStage`]
+/// after items have moved
+fn simulation_systems() -> SystemSet {
+    SystemSet::new()
+        .with_system(
+            delivery_system
+                .after(item_momentum_system)
+                .after(lane_system),
+        )
+        .with_system(completion_system.after(delivery_system))
+}
+
+/// The best score for each part of every completed level, by level name
+#[derive(Debug, Default)]
+pub struct PersonalBests(BTreeMap<String, Score>);
+
+impl PersonalBests {
+    #[must_use]
+    pub fn get(&self, level: &str) -> Option<Score> {
+        self.0.get(level).copied()
+    }
+
+    /// Records a new score for a level, keeping the best of each part
+    pub fn record(&mut self, level: &str, score: Score) {
+        let best = self.get(level).map_or(score, |best| best.best(score));
+        self.0.insert(level.to_owned(), best);
+    }
+
+    fn path() -> Option<PathBuf> {
+        data_dir().map(|dir| dir.join(BESTS_FILE_NAME))
+    }
+
+    /// Loads personal bests from the data directory, starting with none if
+    /// they can't be read
+    #[must_use]
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return PersonalBests::default();
+        };
+        match fs::read_to_string(&path) {
+            Ok(source) => match ron::from_str(&source) {
+                Ok(bests) => PersonalBests(bests),
+                Err(e) => {
+                    warn!("Ignoring invalid personal bests in {}: {e}", path.display());
+                    PersonalBests::default()
+                }
+            },
+            Err(_) => PersonalBests::default(),
+        }
+    }
+
+    /// Saves personal bests to the data directory, logging any errors
+    pub fn save(&self) {
+        let Some(path) = Self::path() else {
+            warn!("No data directory, personal bests will not be saved");
+            return;
+        };
+        if let Err(e) = write_ron(&path, &self.0) {
+            error!("Failed to save personal bests to {}: {e}", path.display());
+        }
+    }
+}
+
+/// Saves the layout that completed a level, so it can be checked or shared
+fn save_solution(level: &str, layout: &Layout) {
+    let Some(dir) = data_dir() else {
+        return;
+    };
+    let path = dir.join(SOLUTIONS_DIR_NAME).join(format!("{level}.ron"));
+    if let Err(e) = write_ron(&path, layout) {
+        error!("Failed to save solution to {}: {e}", path.display());
+    }
+}
+
+/// The outcome of the last completed level, shown on the results screen
+#[derive(Debug)]
+pub struct LevelResults {
+    pub level: usize,
+    pub score: Score,
+    /// The personal bests from before this solution, if the level had been
+    /// completed before
+    pub previous_best: Option<Score>,
+}
+
+/// How close the current game is to meeting its level's goal
+#[derive(Debug, Default, Clone)]
+pub(crate) struct GoalProgress {
+    /// Index of the level being played and its goal, or `None` if the game
+    /// can't be completed
+    goal: Option<(usize, Goal)>,
+    delivered: u32,
+    /// Ticks of each delivery within the last [`THROUGHPUT_SECONDS`]
+    recent: VecDeque<u64>,
+    /// Set once the goal is met
+    score: Option<Score>,
+}
+
+impl GoalProgress {
+    /// How many goal items have been delivered
+    #[must_use]
+    pub fn delivered(&self) -> u32 {
+        self.delivered
+    }
+
+    /// The score of the solution, once the goal has been met
+    #[must_use]
+    pub fn score(&self) -> Option<Score> {
+        self.score
+    }
+}
+
+#[derive(Component)]
+struct GoalText;
+
+pub(crate) fn reset_progress_system(
+    mut progress: ResMut<GoalProgress>,
+    session: Res<Session>,
+    levels: Res<Levels>,
+) {
+    *progress = GoalProgress {
+        goal: session
+            .level
+            .and_then(|index| Some((index, levels.get(index)?.goal.clone()?))),
+        ..default()
+    };
+}
+
+fn setup_system(mut commands: Commands, font: Res<UiFont>) {
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Px(8.0),
+                    bottom: Val::Px(70.0),
+                    ..default()
+                },
+                ..default()
+            },
+            ..font.text("", 20.0, TEXT_COLOR)
+        })
+        .insert(GoalText);
+}
+
+/// Counts items delivered towards the goal
+fn delivery_system(
+    mut item_events: EventReader<ItemEvent>,
+    mut progress: ResMut<GoalProgress>,
+    clock: Res<SimClock>,
+) {
+    let Some((_, goal)) = progress.goal.clone() else {
+        return;
+    };
+    let tick = clock.tick();
+    for _ in item_events
+        .iter()
+        .filter(|e| e.kind == ItemEventKind::Delivered && e.item == goal.item)
+    {
+        progress.delivered += 1;
+        progress.recent.push_back(tick);
+    }
+    let window = THROUGHPUT_SECONDS * u64::from(TICKS_PER_SECOND);
+    while progress.recent.front().is_some_and(|&t| t + window <= tick) {
+        progress.recent.pop_front();
+    }
+}
+
+/// Scores the solution once the goal is met
+fn completion_system(
+    mut progress: ResMut<GoalProgress>,
+    clock: Res<SimClock>,
+    tilemap: Res<Tilemap>,
+) {
+    let Some((_, goal)) = &progress.goal else {
+        return;
+    };
+    if progress.score.is_some() || progress.delivered < goal.count {
+        return;
+    }
+    let throughput = match (progress.recent.front(), progress.recent.back()) {
+        (Some(first), Some(last)) if first != last => {
+            let seconds = (last - first) as f32 / TICKS_PER_SECOND as f32;
+            (progress.recent.len() - 1) as f32 * 60.0 / seconds
+        }
+        _ => 0.0,
+    };
+    let layout = tilemap.layout();
+    progress.score = Some(Score {
+        machines: layout.machines.len() as u32,
+        tiles: layout
+            .machines
+            .iter()
+            .map(|m| m.machine.size().x * m.machine.size().y)
+            .sum(),
+        ticks: clock.tick(),
+        throughput,
+    });
+}
+
+/// Records the score and shows the results once the goal is met
+fn results_system(
+    mut commands: Commands,
+    progress: Res<GoalProgress>,
+    mut bests: ResMut<PersonalBests>,
+    mut state: ResMut<State<AppState>>,
+    levels: Res<Levels>,
+    tilemap: Res<Tilemap>,
+) {
+    let (Some(&(index, _)), Some(score)) = (progress.goal.as_ref(), progress.score) else {
+        return;
+    };
+    let name = &levels.get(index).unwrap().name;
+    let previous_best = bests.get(name);
+    bests.record(name, score);
+    bests.save();
+    save_solution(name, &tilemap.layout());
+    commands.insert_resource(LevelResults {
+        level: index,
+        score,
+        previous_best,
+    });
+    // Takes priority over going back to the main menu on the same frame
+    state.overwrite_set(AppState::Results).unwrap();
+}
+
+fn goal_text_system(progress: Res<GoalProgress>, mut text_query: Query<&mut Text, With<GoalText>>) {
+    if !progress.is_changed() {
+        return;
+    }
+    text_query.single_mut().sections[0].value =
+        progress
+            .goal
+            .as_ref()
+            .map_or_else(String::new, |(_, goal)| {
+                format!(
+                    "Goal: deliver {} {} ({}/{})",
+                    goal.count,
+                    goal.item,
+                    progress.delivered.min(goal.count),
+                    goal.count
+                )
+            });
+}
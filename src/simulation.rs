@@ -0,0 +1,113 @@
+use crate::prelude::*;
+use bevy::{ecs::schedule::ShouldRun, prelude::*};
+
+/// How many times the simulation is updated every second
+pub const TICKS_PER_SECOND: u32 = 60;
+/// Length of a tick, in seconds
+pub const TICK_SECONDS: f32 = 1.0 / TICKS_PER_SECOND as f32;
+/// The most ticks run in one frame, so a slow frame doesn't make the next
+/// one even slower
+const MAX_TICKS_PER_FRAME: u32 = 5;
+
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SimClock>()
+            .add_stage_after(
+                CoreStage::PreUpdate,
+                SimulationStage,
+                SystemStage::parallel().with_run_criteria(tick_run_criteria),
+            )
+            .add_system_set(SystemSet::on_enter(AppState::Game).with_system(reset_system));
+    }
+}
+
+/// Runs once for every tick while in game, so everything in it moves at the
+/// same speed regardless of frame rate. Systems in it should move things by
+/// [`TICK_SECONDS`] rather than the frame time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, StageLabel)]
+pub struct SimulationStage;
+
+/// Runs exactly this many ticks every frame instead of keeping up with real
+/// time, for simulating without a window
+#[derive(Debug, Clone, Copy)]
+pub struct FixedTicks(pub u32);
+
+/// Counts ticks of the current game
+#[derive(Debug, Default)]
+pub struct SimClock {
+    tick: u64,
+    /// Ticks run in the last frame
+    frame_ticks: u32,
+    /// Time that hasn't been simulated yet
+    unsimulated: f32,
+}
+
+impl SimClock {
+    /// The number of the current tick, starting from 1 for the first tick
+    /// of the game
+    #[must_use]
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Goes back to an earlier tick, for when the rest of the simulation
+    /// has been put back to how it was then
+    pub fn rewind(&mut self, tick: u64) {
+        self.tick = tick;
+    }
+
+    /// How many ticks were run in the last frame, once [`SimulationStage`]
+    /// has finished
+    #[must_use]
+    pub fn frame_ticks(&self) -> u32 {
+        self.frame_ticks
+    }
+}
+
+fn reset_system(mut clock: ResMut<SimClock>) {
+    *clock = SimClock::default();
+}
+
+/// Runs [`SimulationStage`] once for every [`TICK_SECONDS`] that has passed
+fn tick_run_criteria(
+    state: Res<State<AppState>>,
+    time: Res<Time>,
+    fixed_ticks: Option<Res<FixedTicks>>,
+    mut clock: ResMut<SimClock>,
+    mut ticks_this_frame: Local<Option<u32>>,
+) -> ShouldRun {
+    if *state.current() != AppState::Game {
+        return ShouldRun::No;
+    }
+    if let Some(fixed_ticks) = fixed_ticks {
+        let ticks = ticks_this_frame.get_or_insert(0);
+        return if *ticks < fixed_ticks.0 {
+            clock.tick += 1;
+            *ticks += 1;
+            ShouldRun::YesAndCheckAgain
+        } else {
+            clock.frame_ticks = *ticks;
+            *ticks_this_frame = None;
+            ShouldRun::No
+        };
+    }
+    let ticks = ticks_this_frame.get_or_insert_with(|| {
+        clock.unsimulated += time.delta_seconds();
+        0
+    });
+    if clock.unsimulated >= TICK_SECONDS && *ticks < MAX_TICKS_PER_FRAME {
+        clock.unsimulated -= TICK_SECONDS;
+        clock.tick += 1;
+        *ticks += 1;
+        ShouldRun::YesAndCheckAgain
+    } else {
+        if *ticks == MAX_TICKS_PER_FRAME {
+            clock.unsimulated = clock.unsimulated.min(TICK_SECONDS);
+        }
+        clock.frame_ticks = *ticks;
+        *ticks_this_frame = None;
+        ShouldRun::No
+    }
+}
@@ -0,0 +1,228 @@
+use crate::{
+    items::Item,
+    prelude::*,
+    simulation::{SimClock, TICKS_PER_SECOND, TICK_SECONDS},
+};
+use bevy::{prelude::*, utils::HashMap};
+use std::collections::VecDeque;
+
+mod panel;
+
+/// Length of each bucket of recorded events, in ticks
+pub const BUCKET_TICKS: u64 = TICKS_PER_SECOND as u64;
+/// Length of each bucket of recorded events, in simulated seconds
+pub const BUCKET_SECONDS: f32 = BUCKET_TICKS as f32 * TICK_SECONDS;
+/// How many buckets are kept, which is also how far back the history goes
+pub const HISTORY_BUCKETS: usize = 60;
+
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(panel::Plugin)
+            .init_resource::<ThroughputStats>()
+            .add_system_set(SystemSet::on_enter(AppState::Game).with_system(reset_system))
+            .add_system_set(SystemSet::on_update(AppState::Game).with_system(record_system));
+    }
+}
+
+/// Something that happened to an item, recorded by [`ThroughputStats`]
+#[derive(Debug, Clone)]
+pub struct ItemEvent {
+    pub kind: ItemEventKind,
+    pub item: Item,
+    /// Position of the machine the event happened in, or `None` if it was
+    /// done by the player
+    pub machine: Option<IVec2>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemEventKind {
+    /// The item was created from nothing
+    Produced,
+    /// The item was taken in by a machine
+    Consumed,
+    /// The item was made by combining other items
+    Combined,
+    /// The item was taken in by a target
+    Delivered,
+    /// The item was made by taking apart another item
+    Disassembled,
+}
+
+/// How many times each kind of [`ItemEvent`] happened
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Counts([u32; 5]);
+
+impl Counts {
+    #[must_use]
+    pub fn get(&self, kind: ItemEventKind) -> u32 {
+        self.0[kind as usize]
+    }
+
+    #[must_use]
+    pub fn total(&self) -> u32 {
+        self.0.iter().sum()
+    }
+
+    fn add(&mut self, kind: ItemEventKind) {
+        self.0[kind as usize] += 1;
+    }
+
+    fn add_counts(&mut self, other: &Counts) {
+        for (a, b) in self.0.iter_mut().zip(other.0) {
+            *a += b;
+        }
+    }
+
+    /// Converts these counts, recorded over `seconds`, to items per minute
+    #[must_use]
+    pub fn per_minute(&self, seconds: f32) -> [f32; 5] {
+        self.0.map(|count| count as f32 * 60.0 / seconds)
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct Bucket {
+    by_item: HashMap<Item, Counts>,
+    by_machine: HashMap<IVec2, Counts>,
+}
+
+/// Item events from the current game, grouped into buckets of
+/// [`BUCKET_TICKS`] so they can be looked at over sliding windows
+#[derive(Debug)]
+pub struct ThroughputStats {
+    /// Oldest first; the last bucket is the one currently being filled
+    buckets: VecDeque<Bucket>,
+    /// How many buckets have been completed this game
+    completed: u64,
+}
+
+impl Default for ThroughputStats {
+    fn default() -> Self {
+        ThroughputStats {
+            buckets: VecDeque::from([Bucket::default()]),
+            completed: 0,
+        }
+    }
+}
+
+impl ThroughputStats {
+    /// Counts an event in the bucket currently being filled
+    pub fn record(&mut self, event: &ItemEvent) {
+        let bucket = self.buckets.back_mut().unwrap();
+        bucket
+            .by_item
+            .entry(event.item.clone())
+            .or_default()
+            .add(event.kind);
+        if let Some(machine) = event.machine {
+            bucket
+                .by_machine
+                .entry(machine)
+                .or_default()
+                .add(event.kind);
+        }
+    }
+
+    /// Moves on to the bucket `tick` is in, completing the buckets before
+    /// it. Going back to an earlier tick drops the buckets after it and
+    /// starts its bucket again
+    pub fn advance_to(&mut self, tick: u64) {
+        let bucket = tick / BUCKET_TICKS;
+        if bucket < self.completed {
+            while self.completed > bucket && self.buckets.len() > 1 {
+                self.buckets.pop_back();
+                self.completed -= 1;
+            }
+            self.completed = bucket;
+            *self.buckets.back_mut().unwrap() = Bucket::default();
+        }
+        while self.completed < bucket {
+            self.completed += 1;
+            self.buckets.push_back(Bucket::default());
+            if self.buckets.len() > HISTORY_BUCKETS + 1 {
+                self.buckets.pop_front();
+            }
+        }
+    }
+
+    /// How many buckets have been completed this game, which only changes
+    /// when the other stats do
+    #[must_use]
+    pub fn completed_buckets(&self) -> u64 {
+        self.completed
+    }
+
+    /// The completed buckets in the last `buckets` buckets, newest first
+    fn window(&self, buckets: usize) -> impl Iterator<Item = &Bucket> {
+        self.buckets.iter().rev().skip(1).take(buckets)
+    }
+
+    /// How many seconds of completed buckets a window of `buckets` covers,
+    /// which is less than requested at the start of a game
+    #[must_use]
+    pub fn window_seconds(&self, buckets: usize) -> f32 {
+        self.window(buckets).count() as f32 * BUCKET_SECONDS
+    }
+
+    /// Counts for each item over the last `buckets` buckets
+    #[must_use]
+    pub fn by_item(&self, buckets: usize) -> Vec<(Item, Counts)> {
+        let mut totals = HashMap::<Item, Counts>::default();
+        for bucket in self.window(buckets) {
+            for (item, counts) in bucket.by_item.iter() {
+                totals.entry(item.clone()).or_default().add_counts(counts);
+            }
+        }
+        let mut totals: Vec<_> = totals.into_iter().collect();
+        totals.sort_by(|(a, _), (b, _)| a.cmp(b));
+        totals
+    }
+
+    /// Counts for each machine over the last `buckets` buckets, busiest first
+    #[must_use]
+    pub fn by_machine(&self, buckets: usize) -> Vec<(IVec2, Counts)> {
+        let mut totals = HashMap::<IVec2, Counts>::default();
+        for bucket in self.window(buckets) {
+            for (machine, counts) in bucket.by_machine.iter() {
+                totals.entry(*machine).or_default().add_counts(counts);
+            }
+        }
+        let mut totals: Vec<_> = totals.into_iter().collect();
+        totals.sort_by_key(|(pos, counts)| (std::cmp::Reverse(counts.total()), pos.y, pos.x));
+        totals
+    }
+
+    /// How many items were produced, combined or taken apart in each
+    /// completed bucket, oldest first
+    pub fn history(&self) -> impl Iterator<Item = u32> + '_ {
+        let completed = self.buckets.len() - 1;
+        self.buckets.iter().take(completed).map(|bucket| {
+            bucket
+                .by_item
+                .values()
+                .map(|c| {
+                    c.get(ItemEventKind::Produced)
+                        + c.get(ItemEventKind::Combined)
+                        + c.get(ItemEventKind::Disassembled)
+                })
+                .sum()
+        })
+    }
+}
+
+fn reset_system(mut stats: ResMut<ThroughputStats>) {
+    *stats = ThroughputStats::default();
+}
+
+fn record_system(
+    mut stats: ResMut<ThroughputStats>,
+    mut item_events: EventReader<ItemEvent>,
+    clock: Res<SimClock>,
+) {
+    for event in item_events.iter() {
+        stats.record(event);
+    }
+    stats.advance_to(clock.tick());
+}
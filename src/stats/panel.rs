@@ -0,0 +1,218 @@
+use super::{Counts, ItemEventKind, ThroughputStats, HISTORY_BUCKETS};
+use crate::{
+    bindings::{Action, ActionInput},
+    prelude::*,
+    tilemap::{Terrain, Tile, Tilemap},
+    ui::{UiFont, DISABLED_TEXT_COLOR, TEXT_COLOR},
+};
+use bevy::prelude::*;
+use std::fmt::Write;
+
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(AppState::Game).with_system(setup_system))
+            .add_system_set(
+                SystemSet::on_update(AppState::Game)
+                    .with_system(toggle_system)
+                    .with_system(update_system),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::Game).with_system(despawn_all_system::<StatsPanel>),
+            );
+    }
+}
+
+#[derive(Component)]
+struct StatsPanel;
+
+#[derive(Component)]
+struct StatsText;
+
+/// One bar of the history graph, oldest first
+#[derive(Component)]
+struct GraphBar(usize);
+
+/// Windows the panel shows rates over, in buckets
+const SHORT_WINDOW: usize = 10;
+const LONG_WINDOW: usize = 60;
+/// How many machines are listed
+const SHOWN_MACHINES: usize = 5;
+
+const PANEL_WIDTH: f32 = 340.0;
+const PANEL_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.75);
+const GRAPH_HEIGHT: f32 = 60.0;
+const GRAPH_COLOR: Color = Color::rgb(0.3, 0.7, 0.3);
+
+fn setup_system(mut commands: Commands, font: Res<UiFont>) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    right: Val::Px(0.0),
+                    top: Val::Px(0.0),
+                    ..default()
+                },
+                size: Size::new(Val::Px(PANEL_WIDTH), Val::Auto),
+                flex_direction: FlexDirection::ColumnReverse,
+                padding: UiRect::all(Val::Px(8.0)),
+                display: Display::None,
+                ..default()
+            },
+            color: PANEL_COLOR.into(),
+            ..default()
+        })
+        .insert(StatsPanel)
+        .with_children(|panel| {
+            panel
+                .spawn_bundle(TextBundle {
+                    text: Text::from_sections([
+                        TextSection::new(
+                            format!(
+                                "Items per minute\nlast {SHORT_WINDOW}s / last {LONG_WINDOW}s\n"
+                            ),
+                            font.style(16.0, DISABLED_TEXT_COLOR),
+                        ),
+                        TextSection::new("", font.style(14.0, TEXT_COLOR)),
+                    ]),
+                    style: Style {
+                        max_size: Size::new(Val::Px(PANEL_WIDTH - 16.0), Val::Undefined),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .insert(StatsText);
+            panel
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(100.0), Val::Px(GRAPH_HEIGHT)),
+                        margin: UiRect::new(Val::Px(0.0), Val::Px(0.0), Val::Px(8.0), Val::Px(0.0)),
+                        flex_direction: FlexDirection::Row,
+                        align_items: AlignItems::FlexStart,
+                        ..default()
+                    },
+                    color: Color::NONE.into(),
+                    ..default()
+                })
+                .with_children(|graph| {
+                    let bar_width = (PANEL_WIDTH - 16.0) / HISTORY_BUCKETS as f32;
+                    for index in 0..HISTORY_BUCKETS {
+                        graph
+                            .spawn_bundle(NodeBundle {
+                                style: Style {
+                                    size: Size::new(Val::Px(bar_width), Val::Px(0.0)),
+                                    ..default()
+                                },
+                                color: GRAPH_COLOR.into(),
+                                ..default()
+                            })
+                            .insert(GraphBar(index));
+                    }
+                });
+        });
+}
+
+fn toggle_system(actions: ActionInput, mut panel_query: Query<&mut Style, With<StatsPanel>>) {
+    if actions.just_pressed(Action::ToggleStats) {
+        let mut style = panel_query.single_mut();
+        style.display = match style.display {
+            Display::None => Display::Flex,
+            Display::Flex => Display::None,
+        };
+    }
+}
+
+/// Formats a pair of rates from the short and long windows
+fn rates(short: [f32; 5], long: [f32; 5], kind: ItemEventKind) -> String {
+    let kind = kind as usize;
+    format!("{:.0} / {:.0}", short[kind], long[kind])
+}
+
+fn update_system(
+    stats: Res<ThroughputStats>,
+    tilemap: Res<Tilemap>,
+    mut text_query: Query<&mut Text, With<StatsText>>,
+    mut bar_query: Query<(&GraphBar, &mut Style)>,
+    mut shown_bucket: Local<Option<u64>>,
+) {
+    if *shown_bucket == Some(stats.completed_buckets()) {
+        return;
+    }
+    *shown_bucket = Some(stats.completed_buckets());
+
+    let short_seconds = stats.window_seconds(SHORT_WINDOW).max(1.0);
+    let long_seconds = stats.window_seconds(LONG_WINDOW).max(1.0);
+    let per_minute =
+        |counts: Option<&Counts>, seconds| counts.copied().unwrap_or_default().per_minute(seconds);
+
+    let short_items = stats.by_item(SHORT_WINDOW);
+    let long_items = stats.by_item(LONG_WINDOW);
+    let mut text = String::new();
+    for (item, long) in long_items.iter() {
+        let short = short_items.iter().find(|(i, _)| i == item).map(|(_, c)| c);
+        let short = per_minute(short, short_seconds);
+        let long = per_minute(Some(long), long_seconds);
+        let _ = writeln!(
+            text,
+            "{item}: made {}, used {}, combined {}, taken apart {}, delivered {}",
+            rates(short, long, ItemEventKind::Produced),
+            rates(short, long, ItemEventKind::Consumed),
+            rates(short, long, ItemEventKind::Combined),
+            rates(short, long, ItemEventKind::Disassembled),
+            rates(short, long, ItemEventKind::Delivered),
+        );
+    }
+    if long_items.is_empty() {
+        text += "No items yet\n";
+    }
+
+    let short_machines = stats.by_machine(SHORT_WINDOW);
+    let long_machines = stats.by_machine(LONG_WINDOW);
+    if !long_machines.is_empty() {
+        text += "\nBusiest machines\n";
+    }
+    for (pos, long) in long_machines.iter().take(SHOWN_MACHINES) {
+        let short = short_machines
+            .iter()
+            .find(|(p, _)| p == pos)
+            .map(|(_, c)| c);
+        let short = per_minute(short, short_seconds);
+        let long = per_minute(Some(long), long_seconds);
+        let tile = tilemap.get_tile(*pos);
+        let name = tile.map_or("Removed machine", |tile| tile.name());
+        // Targets take in items without using them
+        let taken_in = match tile {
+            Some(Tile::Terrain(Terrain::Target, _)) => ItemEventKind::Delivered,
+            _ => ItemEventKind::Consumed,
+        };
+        let made = match tile {
+            Some(Tile::Disassembler(_)) => ItemEventKind::Disassembled,
+            _ => ItemEventKind::Combined,
+        };
+        let _ = writeln!(
+            text,
+            "{name} at ({}, {}): in {}, out {}",
+            pos.x,
+            pos.y,
+            rates(short, long, taken_in),
+            rates(short, long, made),
+        );
+    }
+    text_query.single_mut().sections[1].value = text;
+
+    let history: Vec<_> = stats.history().collect();
+    let max = history.iter().copied().max().unwrap_or(0).max(1);
+    // Right align the history so the newest bucket is always the last bar
+    let offset = HISTORY_BUCKETS - history.len();
+    for (bar, mut style) in bar_query.iter_mut() {
+        let value = bar
+            .0
+            .checked_sub(offset)
+            .and_then(|index| history.get(index))
+            .copied()
+            .unwrap_or(0);
+        style.size.height = Val::Px(GRAPH_HEIGHT * value as f32 / max as f32);
+    }
+}
@@ -1,74 +1,289 @@
-use crate::items::Item;
+use crate::items::{spawn_item, Item, ItemImages};
+use crate::levels::{Levels, Session};
 use crate::prelude::*;
-use bevy::{prelude::*, utils::HashMap};
+use crate::MainCamera;
+use bevy::{prelude::*, sprite::Anchor, utils::HashMap};
+use serde::{Deserialize, Serialize};
 
+pub mod chunks;
+mod combiner;
+mod disassembler;
+pub mod footprint;
+pub mod lanes;
+mod layout;
+mod merger;
 mod setup;
+mod sorter;
 mod transformations;
 
-pub const MIN_MAP_SIZE: usize = 16;
-
+pub use chunks::{chunk_pos, ChunkMap, CHUNK_SIZE};
+pub use combiner::Combiner;
+pub use disassembler::Disassembler;
+pub use footprint::Footprint;
+pub use lanes::{Lanes, ITEM_SPACING};
+pub use layout::*;
+pub use merger::Merger;
+pub use sorter::Sorter;
 pub use transformations::*;
 
 pub struct Plugin;
 
 impl bevy::prelude::Plugin for Plugin {
     fn build(&self, app: &mut App) {
-        app.add_plugin(setup::Plugin);
+        app.add_plugin(HeadlessPlugin)
+            .add_plugin(setup::Plugin)
+            .add_system_set(
+                SystemSet::on_update(AppState::Game)
+                    .with_system(cull_chunks_system)
+                    .with_system(progress_bar_system)
+                    .with_system(machine_sprite_system),
+            );
+    }
+}
+
+/// Loads and saves the session's layout, without drawing the tilemap
+pub(crate) struct HeadlessPlugin;
+
+impl bevy::prelude::Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(AppState::Game).with_system(load_session_system))
+            .add_system_set(SystemSet::on_exit(AppState::Game).with_system(save_session_system));
     }
 }
 
 #[derive(Debug)]
 pub struct Tilemap {
-    data: HashMap<IVec2, Tile>,
+    data: ChunkMap<Tile>,
+    /// The entity each chunk's sprites are children of, so they can be
+    /// hidden together when the chunk is off screen
+    chunk_roots: HashMap<IVec2, Entity>,
+    /// Items on belts
+    lanes: Lanes,
+    /// Where machines can be placed, or `None` if they can be placed anywhere
+    build_area: Option<BuildArea>,
+    /// Sprites darkening everything outside of the build area
+    build_area_shade: Vec<Entity>,
     textures: TextureMap,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A rectangle of tiles machines can be placed in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct BuildArea {
+    /// Corners of the rectangle, both included in it
+    pub min: IVec2,
+    pub max: IVec2,
+}
+
+impl BuildArea {
+    #[must_use]
+    pub fn contains(self, pos: IVec2) -> bool {
+        pos.cmpge(self.min).all() && pos.cmple(self.max).all()
+    }
+}
+
+/// What happened to an item that went off the end of a belt, other than
+/// going onto another belt
+#[derive(Debug, Clone, Copy)]
+pub enum LaneExit {
+    /// The item went into the machine at this position
+    Consumed(IVec2),
+    /// The item slid onto ice at `pos`, going `travel`
+    Dropped { pos: Vec2, travel: Side },
+    /// The item went into the target at this position
+    Delivered(IVec2),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum MachineType {
     Belt,
     Ice,
     Combiner2x1,
+    Combiner3x1,
+    Combiner2x2,
+    Sorter,
+    Merger,
+    Disassembler,
 }
 
 #[derive(Debug)]
 pub enum Tile {
     Belt(Side, Entity),
     Ice(Entity),
-    CombinerInput(CombinerInput),
-    Combiner2x1(Box<Combiner2x1>),
+    /// Covered by the machine placed at this position, which is bigger
+    /// than one tile
+    Part(IVec2),
+    Combiner(Box<Combiner>),
+    Sorter(Box<Sorter>),
+    Merger(Box<Merger>),
+    Disassembler(Box<Disassembler>),
+    Terrain(Terrain, Entity),
 }
 
-#[derive(Debug)]
-pub struct CombinerInput {
-    input_side: Side,
-    parent: IVec2,
+/// Tiles that are part of a level rather than placed by the player, which
+/// can't be built over or removed
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub enum Terrain {
+    /// Stops items
+    Wall,
+    /// Nothing can be built here, and items stop at its edge
+    Void,
+    /// Works like ice placed by the player
+    Ice,
+    /// Takes in items, which count towards the level's goal
+    Target,
+    /// Puts an item onto each belt leading away from it every
+    /// [`crate::items::SOURCE_INTERVAL`] ticks
+    Source(Item),
 }
 
-impl From<CombinerInput> for Tile {
-    fn from(f: CombinerInput) -> Self {
-        Tile::CombinerInput(f)
-    }
+/// Items on belts and in machines, saved by [`Tilemap::held_items`]
+#[derive(Debug, Clone)]
+pub struct HeldItems {
+    lanes: Vec<lanes::LaneItem>,
+    /// What was in each combiner, by position
+    combiners: HashMap<IVec2, combiner::CombinerBuffers>,
+    /// What was in each sorter, by position
+    sorters: HashMap<IVec2, Option<Item>>,
+    /// What was in each merger and whose turn it was, by position
+    mergers: HashMap<IVec2, merger::MergerBuffers>,
+    /// What was in each disassembler, by position
+    disassemblers: HashMap<IVec2, disassembler::DisassemblerBuffers>,
 }
 
-#[derive(Debug)]
-pub struct Combiner2x1 {
-    input_side: Side,
-    inputs: [Option<Item>; 2],
-    entity: Entity,
+/// What a machine is currently doing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MachineStatus {
+    Idle,
+    WaitingForInput,
+    Working,
+    BlockedOutput,
+}
+
+/// A machine that items go into and come out of, kept in its own [`Tile`]
+pub trait Machine: 'static {
+    /// Returns the machine if `tile` is this kind of machine
+    fn from_tile(tile: &Tile) -> Option<&Self>;
+    fn from_tile_mut(tile: &mut Tile) -> Option<&mut Self>;
+    fn machine_type(&self) -> MachineType;
+    fn facing(&self) -> Side;
+    /// The machine's sprite
+    fn entity(&self) -> Entity;
+    /// What the machine is currently doing
+    fn status(&self) -> MachineStatus;
+    /// The items ready to go out, and the side each leaves from
+    fn ready_outputs(&self) -> Vec<(Side, Item)>;
+    /// Records which of the items from [`Machine::ready_outputs`] went out,
+    /// and that anything else was blocked
+    fn finish_outputs(&mut self, output: &[bool]);
+}
+
+/// A machine that takes time to work, which is shown by a progress bar
+pub trait Progress: Machine {
+    /// How far through its work the machine is, from 0 to 1
+    fn progress(&self) -> f32;
+    /// Background of the progress bar, whose child is the bar itself
+    fn progress_bar(&self) -> Entity;
+}
+
+impl Tile {
+    /// The machine on this tile, or `None` for terrain and tiles that are
+    /// only part of a machine
+    #[must_use]
+    pub fn machine_type(&self) -> Option<MachineType> {
+        match self {
+            Tile::Belt(..) => Some(MachineType::Belt),
+            Tile::Ice(_) => Some(MachineType::Ice),
+            Tile::Combiner(c) => Some(c.machine_type()),
+            Tile::Sorter(s) => Some(s.machine_type()),
+            Tile::Merger(m) => Some(m.machine_type()),
+            Tile::Disassembler(d) => Some(d.machine_type()),
+            Tile::Part(_) | Tile::Terrain(..) => None,
+        }
+    }
+
+    /// A short name shown to the player
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Tile::Terrain(terrain, _) => terrain.name(),
+            tile => tile
+                .machine_type()
+                .map_or("Part of a machine", MachineType::name),
+        }
+    }
+
+    /// Whether items keep sliding when on this tile
+    #[must_use]
+    pub fn is_slippery(&self) -> bool {
+        matches!(self, Tile::Ice(_) | Tile::Terrain(Terrain::Ice, _))
+    }
+
+    /// Whether items are stopped before they can move onto this tile
+    #[must_use]
+    pub fn blocks_items(&self) -> bool {
+        matches!(self, Tile::Terrain(Terrain::Wall | Terrain::Void, _))
+    }
+
+    /// The side the machine this tile is part of faces, if it has one
+    #[must_use]
+    pub fn facing(&self) -> Option<Side> {
+        match self {
+            Tile::Belt(side, _) => Some(*side),
+            Tile::Ice(_) => None,
+            Tile::Part(_) => None,
+            Tile::Combiner(c) => Some(c.facing()),
+            Tile::Sorter(s) => Some(s.facing()),
+            Tile::Merger(m) => Some(m.facing()),
+            Tile::Disassembler(d) => Some(d.facing()),
+            Tile::Terrain(..) => None,
+        }
+    }
 }
 
-impl From<Combiner2x1> for Tile {
-    fn from(f: Combiner2x1) -> Self {
-        Tile::Combiner2x1(Box::new(f))
+impl Terrain {
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Terrain::Wall => "Wall",
+            Terrain::Void => "Void",
+            Terrain::Ice => "Ice",
+            Terrain::Target => "Target",
+            Terrain::Source(_) => "Source",
+        }
+    }
+
+    /// Index of this terrain's sprite in the texture atlas
+    #[must_use]
+    pub fn texture(&self, textures: &TextureMap) -> usize {
+        match self {
+            Terrain::Wall => textures.wall,
+            Terrain::Void => textures.void,
+            Terrain::Ice => textures.fixed_ice,
+            Terrain::Target => textures.target,
+            Terrain::Source(_) => textures.source,
+        }
     }
 }
 
-#[derive(Debug)]
+/// Indices of each sprite in the texture atlas. The default has every index
+/// as 0 and no atlas, for when nothing will be drawn
+#[derive(Debug, Default)]
 pub struct TextureMap {
     pub delete_tool: usize,
+    pub inspect_tool: usize,
     pub belt: usize,
     pub ice: usize,
     pub combiner2x1: usize,
+    pub combiner3x1: usize,
+    pub combiner2x2: usize,
+    pub sorter: usize,
+    pub merger: usize,
+    pub disassembler: usize,
+    pub wall: usize,
+    pub void: usize,
+    pub fixed_ice: usize,
+    pub target: usize,
+    pub source: usize,
     pub item_a: usize,
     pub item_b: usize,
     pub item_c: usize,
@@ -79,7 +294,35 @@ pub struct TextureMap {
 #[derive(Debug, Component)]
 struct TileComponent;
 
+const PROGRESS_BAR_HEIGHT: f32 = 0.12;
+const PROGRESS_BAR_COLOR: Color = Color::rgb(0.3, 0.8, 0.3);
+const PROGRESS_BAR_BACKGROUND: Color = Color::rgba(0.0, 0.0, 0.0, 0.6);
+/// Size of the item shown on top of a sorter, in tiles
+const SORTER_FILTER_SIZE: f32 = 0.4;
+/// Tint of machines that can't output because something is in the way
+const BLOCKED_TINT: Color = Color::rgb(1.0, 0.55, 0.55);
+const BUILD_AREA_SHADE_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.5);
+/// How far the shade outside of the build area goes, in tiles
+const BUILD_AREA_SHADE_EXTENT: f32 = 1000.0;
+
+/// The parent of every tile sprite in a chunk
+#[derive(Debug, Component)]
+struct ChunkRoot(IVec2);
+
 impl Tilemap {
+    /// Creates an empty tilemap
+    #[must_use]
+    pub fn new(textures: TextureMap) -> Self {
+        Tilemap {
+            data: default(),
+            chunk_roots: default(),
+            lanes: default(),
+            build_area: None,
+            build_area_shade: Vec::new(),
+            textures,
+        }
+    }
+
     /// Returns the texture atlas this takes textures from
     #[must_use]
     pub fn atlas(&self) -> &Handle<TextureAtlas> {
@@ -94,99 +337,691 @@ impl Tilemap {
 
     #[must_use]
     pub fn get_tile(&self, tile: IVec2) -> Option<&Tile> {
-        self.data.get(&tile)
+        self.data.get(tile)
+    }
+
+    /// Iterates over every tile and its position, in no particular order
+    pub fn tiles(&self) -> impl Iterator<Item = (IVec2, &Tile)> {
+        self.data.iter()
+    }
+
+    /// Iterates over the tiles from `min` to `max`, inclusive, only looking
+    /// at the chunks they are in
+    pub fn tiles_in(&self, min: IVec2, max: IVec2) -> impl Iterator<Item = (IVec2, &Tile)> {
+        self.data
+            .chunks_in(min, max)
+            .flat_map(|chunk| chunk.iter())
+            .filter(move |(pos, _)| pos.cmpge(min).all() && pos.cmple(max).all())
+    }
+
+    /// Returns the position of the machine covering `tile`, which is
+    /// `tile` itself unless a bigger machine was placed on another tile
+    #[must_use]
+    pub fn machine_pos(&self, tile: IVec2) -> IVec2 {
+        match self.data.get(tile) {
+            Some(Tile::Part(parent)) => *parent,
+            _ => tile,
+        }
+    }
+
+    /// Returns the machine of type `M` placed at `pos`
+    #[must_use]
+    pub fn machine<M: Machine>(&self, pos: IVec2) -> Option<&M> {
+        M::from_tile(self.data.get(pos)?)
+    }
+
+    pub fn machine_mut<M: Machine>(&mut self, pos: IVec2) -> Option<&mut M> {
+        M::from_tile_mut(self.data.get_mut(pos)?)
+    }
+
+    /// Iterates over every machine of type `M` and its position, ordered by
+    /// position so machines sharing an output always take turns the same way
+    pub fn machines<M: Machine>(&self) -> impl Iterator<Item = (IVec2, &M)> {
+        let mut machines: Vec<_> = self
+            .data
+            .iter()
+            .filter_map(|(pos, tile)| Some((pos, M::from_tile(tile)?)))
+            .collect();
+        machines.sort_by_key(|(pos, _)| (pos.y, pos.x));
+        machines.into_iter()
+    }
+
+    /// [`Tilemap::machines`], borrowing each machine mutably
+    pub fn machines_mut<M: Machine>(&mut self) -> impl Iterator<Item = (IVec2, &mut M)> {
+        let mut machines: Vec<_> = self
+            .data
+            .iter_mut()
+            .filter_map(|(pos, tile)| Some((pos, M::from_tile_mut(tile)?)))
+            .collect();
+        machines.sort_by_key(|(pos, _)| (pos.y, pos.x));
+        machines.into_iter()
+    }
+
+    /// Returns the combiner covering `tile`
+    #[must_use]
+    pub fn combiner(&self, tile: IVec2) -> Option<&Combiner> {
+        self.machine(self.machine_pos(tile))
+    }
+
+    /// Returns the position of the combiner covering `tile`, the index of
+    /// the input on that tile and the combiner itself
+    pub fn combiner_mut(&mut self, tile: IVec2) -> Option<(IVec2, usize, &mut Combiner)> {
+        combiner::combiner_in(&mut self.data, tile)
+    }
+
+    /// Returns the items on belts
+    #[must_use]
+    pub fn lanes(&self) -> &Lanes {
+        &self.lanes
+    }
+
+    pub fn lanes_mut(&mut self) -> &mut Lanes {
+        &mut self.lanes
+    }
+
+    /// Moves items on belts `distance` tiles. Items going off the end of a
+    /// belt go into machines or onto ice if they can, which is returned
+    pub fn update_lanes(&mut self, distance: f32) -> Vec<(Item, LaneExit)> {
+        let Tilemap { data, lanes, .. } = self;
+        let mut exits = Vec::new();
+        lanes.update(distance, |tile, travel, item| match data.get(tile) {
+            Some(tile_data) if tile_data.is_slippery() => {
+                // Just inside the ice, so the item isn't put back on the belt
+                let pos = tile.as_vec2() - travel.to_vec2() * 0.49;
+                exits.push((item, LaneExit::Dropped { pos, travel }));
+                true
+            }
+            Some(Tile::Part(_) | Tile::Combiner(_)) => {
+                let Some((pos, input, combiner)) = combiner::combiner_in(data, tile) else {
+                    return false;
+                };
+                let inserted = combiner.try_insert(input, travel, item.clone()).is_ok();
+                if inserted {
+                    exits.push((item, LaneExit::Consumed(pos)));
+                }
+                inserted
+            }
+            Some(Tile::Sorter(_)) => match data.get_mut(tile) {
+                Some(Tile::Sorter(sorter)) => sorter.try_insert(travel, item).is_ok(),
+                _ => unreachable!(),
+            },
+            Some(Tile::Merger(_)) => match data.get_mut(tile) {
+                Some(Tile::Merger(merger)) => merger.try_insert(travel, item).is_ok(),
+                _ => unreachable!(),
+            },
+            Some(Tile::Disassembler(_)) => match data.get_mut(tile) {
+                Some(Tile::Disassembler(d)) => {
+                    let inserted = d.try_insert(travel, item.clone()).is_ok();
+                    if inserted {
+                        exits.push((item, LaneExit::Consumed(tile)));
+                    }
+                    inserted
+                }
+                _ => unreachable!(),
+            },
+            Some(Tile::Terrain(Terrain::Target, _)) => {
+                exits.push((item, LaneExit::Delivered(tile)));
+                true
+            }
+            _ => false,
+        });
+        exits
+    }
+
+    /// Saves the items on belts and in machines, so they can be put back
+    /// with [`Tilemap::restore_held_items`]
+    #[must_use]
+    pub fn held_items(&self) -> HeldItems {
+        HeldItems {
+            lanes: self.lanes.lane_items(),
+            combiners: self
+                .machines::<Combiner>()
+                .map(|(pos, combiner)| (pos, combiner.buffers()))
+                .collect(),
+            sorters: self
+                .machines::<Sorter>()
+                .map(|(pos, sorter)| (pos, sorter.held().cloned()))
+                .collect(),
+            mergers: self
+                .machines::<Merger>()
+                .map(|(pos, merger)| (pos, merger.buffers()))
+                .collect(),
+            disassemblers: self
+                .machines::<Disassembler>()
+                .map(|(pos, disassembler)| (pos, disassembler.buffers()))
+                .collect(),
+        }
+    }
+
+    /// Puts back items saved by [`Tilemap::held_items`] in place of the
+    /// ones held now, keeping the current machines. Items on belts that
+    /// have since been removed are left where they were, and machines that
+    /// weren't there yet are emptied
+    pub fn restore_held_items(&mut self, held: &HeldItems, commands: &mut Commands) {
+        for (pos, item) in self.lanes.set_items(&held.lanes) {
+            spawn_item(commands, item, pos, Vec2::ZERO);
+        }
+        for (pos, combiner) in self.machines_mut::<Combiner>() {
+            combiner.set_buffers(held.combiners.get(&pos));
+        }
+        for (pos, sorter) in self.machines_mut::<Sorter>() {
+            sorter.set_held(held.sorters.get(&pos).cloned().flatten());
+        }
+        for (pos, merger) in self.machines_mut::<Merger>() {
+            merger.set_buffers(held.mergers.get(&pos));
+        }
+        for (pos, disassembler) in self.machines_mut::<Disassembler>() {
+            disassembler.set_buffers(held.disassemblers.get(&pos));
+        }
+    }
+
+    /// Regroups every belt into lanes after a layout was loaded. Items on
+    /// removed belts are left where they were
+    fn rebuild_lanes(&mut self, commands: &mut Commands) {
+        let belts = self.data.iter().filter_map(|(pos, tile)| match tile {
+            Tile::Belt(side, _) => Some((pos, *side)),
+            _ => None,
+        });
+        for (pos, item) in self.lanes.rebuild(belts) {
+            spawn_item(commands, item, pos, Vec2::ZERO);
+        }
     }
 
-    /// Adds a tile to the tilemap if there is space for it
+    /// Adds a tile to the tilemap if there is space for it, returning
+    /// whether it was added
     pub fn try_add(
         &mut self,
         pos: IVec2,
         tile: MachineType,
         facing_side: Side,
         commands: &mut Commands,
-    ) {
+    ) -> bool {
+        let added = self.add_without_lanes(pos, tile, facing_side, commands);
+        if added && tile == MachineType::Belt {
+            self.lanes.add_belt(pos, facing_side);
+        }
+        added
+    }
+
+    /// Whether every tile of a machine would be inside the build area
+    #[must_use]
+    pub fn in_build_area(&self, pos: IVec2, machine: MachineType, facing_side: Side) -> bool {
+        self.build_area.is_none_or(|area| {
+            machine
+                .tiles(pos, facing_side)
+                .into_iter()
+                .all(|tile| area.contains(tile))
+        })
+    }
+
+    /// [`Tilemap::try_add`], without regrouping belts into lanes
+    fn add_without_lanes(
+        &mut self,
+        pos: IVec2,
+        tile: MachineType,
+        facing_side: Side,
+        commands: &mut Commands,
+    ) -> bool {
+        if !self.in_build_area(pos, tile, facing_side)
+            || tile
+                .tiles(pos, facing_side)
+                .into_iter()
+                .any(|tile| self.data.contains(tile))
+        {
+            return false;
+        }
         let mut spawn_rect = |index, z, size, offset: Vec2| {
             let mut transform = transform_from_grid_pos(pos, z, facing_side);
 
             transform.translation += offset.extend(0.0);
-            commands
-                .spawn_bundle(SpriteSheetBundle {
-                    transform,
-                    sprite: TextureAtlasSprite {
-                        index,
-                        custom_size: Some(size),
-                        ..default()
-                    },
-                    texture_atlas: self.textures.atlas.clone(),
+            spawn_tile_sprite(
+                commands,
+                &mut self.chunk_roots,
+                &self.textures.atlas,
+                pos,
+                TextureAtlasSprite {
+                    index,
+                    custom_size: Some(size),
                     ..default()
-                })
-                .id()
+                },
+                transform,
+            )
         };
         let mut spawn_square = |index, z| spawn_rect(index, z, Vec2::ONE, Vec2::ZERO);
         match tile {
             MachineType::Belt => {
-                self.data.entry(pos).or_insert_with(|| {
-                    let entity = spawn_square(self.textures.belt, 2.0);
-                    Tile::Belt(facing_side, entity)
-                });
+                let entity = spawn_square(self.textures.belt, 2.0);
+                self.data.insert(pos, Tile::Belt(facing_side, entity));
             }
             MachineType::Ice => {
-                self.data.entry(pos).or_insert_with(|| {
-                    let entity = spawn_square(self.textures.ice, 2.0);
-                    Tile::Ice(entity)
-                });
-            }
-            MachineType::Combiner2x1 => {
-                let input_side = facing_side.opposite();
-                if !self.data.contains_key(&pos)
-                    && !self
-                        .data
-                        .contains_key(&(pos + input_side.rotate_right().to_ivec2()))
-                {
-                    let entity = spawn_rect(
-                        self.textures.combiner2x1,
-                        4.0,
-                        Vec2::new(2.0, 1.0),
-                        facing_side.rotate_vec2(MachineType::Combiner2x1.cursor_offset()),
-                    );
-                    self.data.insert(
-                        pos,
-                        Combiner2x1 {
-                            input_side,
-                            inputs: [None, None],
-                            entity,
-                        }
-                        .into(),
-                    );
-                    self.data.insert(
-                        pos + input_side.rotate_right().to_ivec2(),
-                        CombinerInput {
-                            input_side,
-                            parent: pos,
-                        }
-                        .into(),
-                    );
+                let entity = spawn_square(self.textures.ice, 2.0);
+                self.data.insert(pos, Tile::Ice(entity));
+            }
+            MachineType::Combiner2x1 | MachineType::Combiner3x1 | MachineType::Combiner2x2 => {
+                let footprint = tile.footprint();
+                let texture = match tile {
+                    MachineType::Combiner3x1 => self.textures.combiner3x1,
+                    MachineType::Combiner2x2 => self.textures.combiner2x2,
+                    _ => self.textures.combiner2x1,
+                };
+                let size = footprint.size().as_vec2();
+                let entity = spawn_rect(
+                    texture,
+                    4.0,
+                    size,
+                    facing_side.rotate_vec2(footprint.center_offset()),
+                );
+                let progress_bar = spawn_progress_bar(commands, entity, size);
+                self.data.insert(
+                    pos,
+                    Combiner::new(tile, facing_side, entity, progress_bar).into(),
+                );
+            }
+            MachineType::Sorter => {
+                let entity = spawn_square(self.textures.sorter, 2.0);
+                let filter = Item::A;
+                let overlay = commands
+                    .spawn_bundle(SpriteBundle {
+                        sprite: Sprite {
+                            custom_size: Some(Vec2::splat(SORTER_FILTER_SIZE)),
+                            ..default()
+                        },
+                        // Undo the machine's rotation so the item stays upright
+                        transform: Transform::from_xyz(0.0, 0.0, 0.1)
+                            .with_rotation(Quat::from_rotation_z(-facing_side.to_angle())),
+                        // Shown once its image is set
+                        visibility: Visibility { is_visible: false },
+                        ..default()
+                    })
+                    .id();
+                commands.entity(entity).add_child(overlay);
+                self.data.insert(
+                    pos,
+                    Sorter::new(facing_side, filter, entity, overlay).into(),
+                );
+            }
+            MachineType::Disassembler => {
+                let entity = spawn_square(self.textures.disassembler, 2.0);
+                let progress_bar = spawn_progress_bar(commands, entity, Vec2::ONE);
+                self.data.insert(
+                    pos,
+                    Disassembler::new(facing_side, entity, progress_bar).into(),
+                );
+            }
+            MachineType::Merger => {
+                let entity = spawn_square(self.textures.merger, 2.0);
+                self.data
+                    .insert(pos, Merger::new(facing_side, entity).into());
+            }
+        }
+        for part in tile.tiles(pos, facing_side).into_iter().skip(1) {
+            self.data.insert(part, Tile::Part(pos));
+        }
+        true
+    }
+
+    /// Changes where machines can be placed, without removing any machines
+    /// already outside of it
+    pub fn set_build_area(&mut self, build_area: Option<BuildArea>, commands: &mut Commands) {
+        for entity in self.build_area_shade.drain(..) {
+            commands.entity(entity).despawn();
+        }
+        self.build_area = build_area;
+        let Some(area) = build_area else {
+            return;
+        };
+        // Edges of the area, halfway between the tiles inside and outside it
+        let min = area.min.as_vec2() - Vec2::splat(0.5);
+        let max = area.max.as_vec2() + Vec2::splat(0.5);
+        let far = Vec2::splat(BUILD_AREA_SHADE_EXTENT);
+        let rects = [
+            // Above and below, including the corners
+            (Vec2::new(min.x, max.y) - Vec2::new(far.x, 0.0), max + far),
+            (min - far, Vec2::new(max.x, min.y) + Vec2::new(far.x, 0.0)),
+            // Left and right
+            (min - Vec2::new(far.x, 0.0), Vec2::new(min.x, max.y)),
+            (Vec2::new(max.x, min.y), max + Vec2::new(far.x, 0.0)),
+        ];
+        for (min, max) in rects {
+            let entity = commands
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: BUILD_AREA_SHADE_COLOR,
+                        custom_size: Some(max - min),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(((min + max) / 2.0).extend(5.0)),
+                    ..default()
+                })
+                .id();
+            self.build_area_shade.push(entity);
+        }
+    }
+
+    /// Adds terrain to the tilemap, replacing whatever was there
+    pub fn add_terrain(&mut self, pos: IVec2, terrain: Terrain, commands: &mut Commands) {
+        match self.data.remove(pos) {
+            Some(Tile::Terrain(_, entity)) => commands.entity(entity).despawn_recursive(),
+            Some(tile) => {
+                self.data.insert(pos, tile);
+                self.remove(pos, commands);
+            }
+            None => (),
+        }
+        let entity = spawn_tile_sprite(
+            commands,
+            &mut self.chunk_roots,
+            &self.textures.atlas,
+            pos,
+            TextureAtlasSprite {
+                index: terrain.texture(&self.textures),
+                custom_size: Some(Vec2::ONE),
+                ..default()
+            },
+            transform_from_grid_pos(pos, 1.0, Side::North),
+        );
+        self.data.insert(pos, Tile::Terrain(terrain, entity));
+    }
+
+    /// Removes a tile from the tilemap, unless it is terrain, returning the
+    /// machine that was removed
+    pub fn remove(&mut self, pos: IVec2, commands: &mut Commands) -> Option<MachineType> {
+        match self.data.get(pos) {
+            Some(Tile::Terrain(..)) => return None,
+            Some(&Tile::Part(parent)) => return self.remove(parent, commands),
+            _ => (),
+        }
+        let tile = self.data.remove(pos)?;
+        let facing = tile.facing().unwrap_or(Side::North);
+        let removed = match tile {
+            Tile::Belt(_, entity) => {
+                commands.entity(entity).despawn_recursive();
+                for (pos, item) in self.lanes.remove_belt(pos) {
+                    spawn_item(commands, item, pos, Vec2::ZERO);
                 }
+                MachineType::Belt
+            }
+            Tile::Ice(entity) => {
+                commands.entity(entity).despawn_recursive();
+                MachineType::Ice
+            }
+            Tile::Part(_) | Tile::Terrain(..) => unreachable!(),
+            Tile::Combiner(c) => {
+                commands.entity(c.entity()).despawn_recursive();
+                c.machine_type()
             }
+            Tile::Sorter(s) => {
+                commands.entity(s.entity()).despawn_recursive();
+                MachineType::Sorter
+            }
+            Tile::Merger(m) => {
+                commands.entity(m.entity()).despawn_recursive();
+                MachineType::Merger
+            }
+            Tile::Disassembler(d) => {
+                commands.entity(d.entity()).despawn_recursive();
+                MachineType::Disassembler
+            }
+        };
+        for part in removed.tiles(pos, facing).into_iter().skip(1) {
+            self.data.remove(part);
+            self.free_chunk_root(part, commands);
         }
+        self.free_chunk_root(pos, commands);
+        Some(removed)
     }
 
-    /// Removes a tile from the tilemap
-    pub fn remove(&mut self, pos: IVec2, commands: &mut Commands) {
-        match self.data.remove(&pos) {
-            None => return,
-            Some(Tile::Belt(_, entity)) | Some(Tile::Ice(entity)) => {
-                commands.entity(entity).despawn();
+    /// Despawns the root of the chunk containing `tile` if the chunk is empty
+    fn free_chunk_root(&mut self, tile: IVec2, commands: &mut Commands) {
+        let chunk = chunk_pos(tile);
+        if self.data.chunk(chunk).is_none() {
+            if let Some(root) = self.chunk_roots.remove(&chunk) {
+                commands.entity(root).despawn_recursive();
             }
-            Some(Tile::CombinerInput(c)) => self.remove(c.parent, commands),
-            Some(Tile::Combiner2x1(c)) => {
-                commands.entity(c.entity).despawn();
-                self.data
-                    .remove(&(pos + c.input_side.rotate_right().to_ivec2()));
+        }
+    }
+
+    /// Removes every tile from the tilemap
+    pub fn clear(&mut self, commands: &mut Commands) {
+        for (_, root) in self.chunk_roots.drain() {
+            commands.entity(root).despawn_recursive();
+        }
+        self.data.clear();
+        self.lanes = default();
+        self.set_build_area(None, commands);
+    }
+
+    /// Returns every machine on the tilemap, ordered by position
+    #[must_use]
+    pub fn layout(&self) -> Layout {
+        let mut machines: Vec<_> = self
+            .tiles()
+            .filter_map(|(pos, tile)| {
+                let (machine, facing, filter) = match tile {
+                    Tile::Belt(side, _) => (MachineType::Belt, *side, None),
+                    Tile::Ice(_) => (MachineType::Ice, Side::North, None),
+                    Tile::Part(_) | Tile::Terrain(..) => return None,
+                    Tile::Combiner(c) => (c.machine_type(), c.facing(), None),
+                    Tile::Sorter(s) => (MachineType::Sorter, s.facing(), Some(s.filter().clone())),
+                    Tile::Merger(m) => (MachineType::Merger, m.facing(), None),
+                    Tile::Disassembler(d) => (MachineType::Disassembler, d.facing(), None),
+                };
+                Some(PlacedMachine {
+                    pos,
+                    machine,
+                    facing,
+                    filter,
+                })
+            })
+            .collect();
+        machines.sort_by_key(|m| (m.pos.y, m.pos.x));
+        Layout { machines }
+    }
+
+    /// Adds every machine in `layout` to the tilemap, skipping any that overlap
+    pub fn load_layout(&mut self, layout: &Layout, commands: &mut Commands) {
+        for m in layout.machines.iter() {
+            let added = self.add_without_lanes(m.pos, m.machine, m.facing, commands);
+            if let (true, Some(filter)) = (added, m.filter.clone()) {
+                if let Some(sorter) = self.machine_mut::<Sorter>(m.pos) {
+                    sorter.set_filter(filter);
+                }
+            }
+        }
+        self.rebuild_lanes(commands);
+    }
+}
+
+/// Spawns the sprite of a tile as a child of the root of its chunk
+fn spawn_tile_sprite(
+    commands: &mut Commands,
+    chunk_roots: &mut HashMap<IVec2, Entity>,
+    atlas: &Handle<TextureAtlas>,
+    pos: IVec2,
+    sprite: TextureAtlasSprite,
+    transform: Transform,
+) -> Entity {
+    let entity = commands
+        .spawn_bundle(SpriteSheetBundle {
+            transform,
+            sprite,
+            texture_atlas: atlas.clone(),
+            ..default()
+        })
+        .insert(TileComponent)
+        .id();
+    let chunk = chunk_pos(pos);
+    let root = *chunk_roots.entry(chunk).or_insert_with(|| {
+        commands
+            .spawn_bundle(SpatialBundle::default())
+            .insert(ChunkRoot(chunk))
+            .id()
+    });
+    commands.entity(root).add_child(entity);
+    entity
+}
+
+/// Spawns a hidden progress bar along the bottom of a machine's sprite of
+/// `machine_size` tiles, returning its background
+fn spawn_progress_bar(commands: &mut Commands, machine: Entity, machine_size: Vec2) -> Entity {
+    let width = machine_size.x - 0.4;
+    let size = Some(Vec2::new(width, PROGRESS_BAR_HEIGHT));
+    let bar = commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: PROGRESS_BAR_COLOR,
+                custom_size: size,
+                anchor: Anchor::CenterLeft,
+                ..default()
+            },
+            transform: Transform::from_xyz(-width / 2.0, 0.0, 0.1),
+            ..default()
+        })
+        .id();
+    let background = commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: PROGRESS_BAR_BACKGROUND,
+                custom_size: size,
+                ..default()
+            },
+            transform: Transform::from_xyz(0.0, -machine_size.y / 2.0 + 0.12, 1.0),
+            visibility: Visibility { is_visible: false },
+            ..default()
+        })
+        .add_child(bar)
+        .id();
+    commands.entity(machine).add_child(background);
+    background
+}
+
+pub(crate) fn load_session_system(
+    mut commands: Commands,
+    mut tilemap: ResMut<Tilemap>,
+    session: Res<Session>,
+    levels: Res<Levels>,
+) {
+    if let Some(level) = session.level.and_then(|index| levels.get(index)) {
+        tilemap.set_build_area(level.build_area, &mut commands);
+        for area in level.terrain.iter() {
+            for pos in area.tiles() {
+                tilemap.add_terrain(pos, area.terrain.clone(), &mut commands);
+            }
+        }
+    }
+    tilemap.load_layout(&session.layout, &mut commands);
+}
+
+pub(crate) fn save_session_system(
+    mut commands: Commands,
+    mut tilemap: ResMut<Tilemap>,
+    mut session: ResMut<Session>,
+) {
+    session.layout = tilemap.layout();
+    tilemap.clear(&mut commands);
+}
+
+/// Shows how far through its work each machine is, and tints machines
+/// with a blocked output
+fn progress_bar_system(
+    tilemap: Res<Tilemap>,
+    mut background_query: Query<(&mut Visibility, &Children)>,
+    mut bar_query: Query<&mut Transform>,
+    mut sprite_query: Query<&mut TextureAtlasSprite>,
+) {
+    show_progress::<Combiner>(
+        &tilemap,
+        &mut background_query,
+        &mut bar_query,
+        &mut sprite_query,
+    );
+    show_progress::<Disassembler>(
+        &tilemap,
+        &mut background_query,
+        &mut bar_query,
+        &mut sprite_query,
+    );
+}
+
+/// Updates the progress bar of every machine of type `M`, and tints the
+/// ones with a blocked output
+fn show_progress<M: Progress>(
+    tilemap: &Tilemap,
+    background_query: &mut Query<(&mut Visibility, &Children)>,
+    bar_query: &mut Query<&mut Transform>,
+    sprite_query: &mut Query<&mut TextureAtlasSprite>,
+) {
+    for (_, machine) in tilemap.machines::<M>() {
+        let progress = machine.progress();
+        // Missing for the frame the machine is placed on
+        if let Ok((mut visibility, children)) = background_query.get_mut(machine.progress_bar()) {
+            if visibility.is_visible != (progress > 0.0) {
+                visibility.is_visible = progress > 0.0;
+            }
+            let mut transform = bar_query.get_mut(children[0]).unwrap();
+            if transform.scale.x != progress {
+                transform.scale.x = progress;
             }
         }
-        if self.data.capacity() > MIN_MAP_SIZE.max(self.data.len()) {
-            self.data.shrink_to(MIN_MAP_SIZE.max(self.data.len() + 8));
+        tint_blocked(sprite_query, machine.entity(), machine.status());
+    }
+}
+
+/// Tints a machine's sprite if its output is blocked
+fn tint_blocked(
+    sprite_query: &mut Query<&mut TextureAtlasSprite>,
+    entity: Entity,
+    status: MachineStatus,
+) {
+    // Missing for the frame the machine is placed on
+    if let Ok(mut sprite) = sprite_query.get_mut(entity) {
+        let color = match status {
+            MachineStatus::BlockedOutput => BLOCKED_TINT,
+            _ => Color::WHITE,
+        };
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
+
+/// Shows each sorter's filter on top of it, and tints sorters and mergers
+/// with a blocked output
+fn machine_sprite_system(
+    tilemap: Res<Tilemap>,
+    mut images: ItemImages,
+    mut sprite_query: Query<&mut TextureAtlasSprite>,
+    mut overlay_query: Query<(&mut Handle<Image>, &mut Visibility)>,
+) {
+    for (_, sorter) in tilemap.machines::<Sorter>() {
+        if let Ok((mut overlay, mut visibility)) = overlay_query.get_mut(sorter.overlay()) {
+            let image = images.get(sorter.filter(), &tilemap.textures);
+            if *overlay != image {
+                *overlay = image;
+                visibility.is_visible = true;
+            }
+        }
+        tint_blocked(&mut sprite_query, sorter.entity(), sorter.status());
+    }
+    for (_, merger) in tilemap.machines::<Merger>() {
+        tint_blocked(&mut sprite_query, merger.entity(), merger.status());
+    }
+}
+
+/// Hides chunks that are off screen
+fn cull_chunks_system(
+    camera_query: Query<(&OrthographicProjection, &GlobalTransform), With<MainCamera>>,
+    mut chunk_query: Query<(&ChunkRoot, &mut Visibility)>,
+) {
+    let (projection, camera_transform) = camera_query.single();
+    let (min, max) = camera_view_rect(projection, camera_transform);
+    // Sprites can stick out of their chunk by up to a tile
+    let min = chunk_pos(world_to_grid_pos(min - Vec2::ONE).tile);
+    let max = chunk_pos(world_to_grid_pos(max + Vec2::ONE).tile);
+    for (root, mut visibility) in chunk_query.iter_mut() {
+        let is_visible = root.0.cmpge(min).all() && root.0.cmple(max).all();
+        if visibility.is_visible != is_visible {
+            visibility.is_visible = is_visible;
         }
     }
 }
@@ -197,18 +1032,68 @@ impl MachineType {
     pub fn size(self) -> UVec2 {
         use MachineType::*;
         match self {
-            Belt | Ice => UVec2::ONE,
+            Belt | Ice | Sorter | Merger | Disassembler => UVec2::ONE,
             Combiner2x1 => UVec2::new(2, 1),
+            Combiner3x1 => UVec2::new(3, 1),
+            Combiner2x2 => UVec2::new(2, 2),
         }
     }
 
+    /// The tiles this machine covers
+    #[must_use]
+    pub fn footprint(self) -> Footprint {
+        Footprint::new(self.size())
+    }
+
+    /// Every tile a machine placed at `pos` would cover, starting with `pos`
+    #[must_use]
+    pub fn tiles(self, pos: IVec2, facing_side: Side) -> Vec<IVec2> {
+        self.footprint().tiles(pos, facing_side).collect()
+    }
+
     /// Offset of the center of this sprite from it's grid position
     #[must_use]
     pub fn cursor_offset(self) -> Vec2 {
+        self.footprint().center_offset()
+    }
+
+    /// The name of this machine shown to the player
+    #[must_use]
+    pub fn name(self) -> &'static str {
         use MachineType::*;
         match self {
-            Combiner2x1 => Vec2::new(-0.5, 0.0),
-            _ => Vec2::ZERO,
+            Belt => "Belt",
+            Ice => "Ice",
+            Combiner2x1 => "Combiner",
+            Combiner3x1 => "Triple Combiner",
+            Combiner2x2 => "Quad Combiner",
+            Sorter => "Sorter",
+            Merger => "Merger",
+            Disassembler => "Disassembler",
+        }
+    }
+
+    /// A short explanation of what this machine does, shown to the player
+    #[must_use]
+    pub fn description(self) -> &'static str {
+        use MachineType::*;
+        match self {
+            Belt => "Carries items in the direction it faces.",
+            Ice => "Items keep sliding across ice in whatever direction they were moving.",
+            Combiner2x1 => "Takes an item into each of its two inputs from its back or sides and combines them into one.",
+            Combiner3x1 => {
+                "Takes an item into each of its three inputs from its back or sides and combines them into one."
+            }
+            Combiner2x2 => "Takes an item into each of its four tiles from its back or sides and combines them into one.",
+            Sorter => {
+                "Takes items from its back and sends those matching its filter out of its right side, and everything else straight through."
+            }
+            Disassembler => {
+                "Takes apart items from its back into what they were made from, which leave from its sides and front."
+            }
+            Merger => {
+                "Takes items from its back and both sides and sends them out of its front, taking turns between them."
+            }
         }
     }
 }
@@ -0,0 +1,183 @@
+use bevy::{prelude::*, utils::HashMap};
+
+/// Width and height of a chunk, in tiles
+pub const CHUNK_SIZE: i32 = 32;
+const CHUNK_AREA: usize = (CHUNK_SIZE * CHUNK_SIZE) as usize;
+
+/// A sparse grid of `T`, stored in [`CHUNK_SIZE`] square chunks so nearby
+/// tiles are next to each other in memory. Empty chunks are freed.
+#[derive(Debug, Clone)]
+pub struct ChunkMap<T> {
+    chunks: HashMap<IVec2, Chunk<T>>,
+    len: usize,
+}
+
+/// One [`CHUNK_SIZE`] square of a [`ChunkMap`]
+#[derive(Debug, Clone)]
+pub struct Chunk<T> {
+    pos: IVec2,
+    tiles: Box<[Option<T>]>,
+    len: usize,
+}
+
+/// Returns the chunk containing `tile`
+#[must_use]
+pub fn chunk_pos(tile: IVec2) -> IVec2 {
+    IVec2::new(tile.x.div_euclid(CHUNK_SIZE), tile.y.div_euclid(CHUNK_SIZE))
+}
+
+/// Returns the index of `tile` in its chunk
+fn tile_index(tile: IVec2) -> usize {
+    let local = IVec2::new(tile.x.rem_euclid(CHUNK_SIZE), tile.y.rem_euclid(CHUNK_SIZE));
+    (local.y * CHUNK_SIZE + local.x) as usize
+}
+
+impl<T> Chunk<T> {
+    fn new(pos: IVec2) -> Self {
+        Chunk {
+            pos,
+            tiles: (0..CHUNK_AREA).map(|_| None).collect(),
+            len: 0,
+        }
+    }
+
+    /// Position of this chunk, in chunks
+    #[must_use]
+    pub fn pos(&self) -> IVec2 {
+        self.pos
+    }
+
+    /// The first tile of this chunk
+    fn origin(&self) -> IVec2 {
+        self.pos * CHUNK_SIZE
+    }
+
+    /// How many tiles are in this chunk
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Chunks in a [`ChunkMap`] are never empty, since empty chunks are freed
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Iterates over every tile in this chunk and its position, ordered by
+    /// position
+    pub fn iter(&self) -> impl Iterator<Item = (IVec2, &T)> {
+        let origin = self.origin();
+        self.tiles.iter().enumerate().filter_map(move |(i, tile)| {
+            let local = IVec2::new(i as i32 % CHUNK_SIZE, i as i32 / CHUNK_SIZE);
+            tile.as_ref().map(|tile| (origin + local, tile))
+        })
+    }
+
+    fn iter_mut(&mut self) -> impl Iterator<Item = (IVec2, &mut T)> {
+        let origin = self.origin();
+        self.tiles
+            .iter_mut()
+            .enumerate()
+            .filter_map(move |(i, tile)| {
+                let local = IVec2::new(i as i32 % CHUNK_SIZE, i as i32 / CHUNK_SIZE);
+                tile.as_mut().map(|tile| (origin + local, tile))
+            })
+    }
+}
+
+impl<T> Default for ChunkMap<T> {
+    fn default() -> Self {
+        ChunkMap {
+            chunks: HashMap::default(),
+            len: 0,
+        }
+    }
+}
+
+impl<T> ChunkMap<T> {
+    /// How many tiles are in the map
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[must_use]
+    pub fn get(&self, tile: IVec2) -> Option<&T> {
+        self.chunks.get(&chunk_pos(tile))?.tiles[tile_index(tile)].as_ref()
+    }
+
+    pub fn get_mut(&mut self, tile: IVec2) -> Option<&mut T> {
+        self.chunks.get_mut(&chunk_pos(tile))?.tiles[tile_index(tile)].as_mut()
+    }
+
+    #[must_use]
+    pub fn contains(&self, tile: IVec2) -> bool {
+        self.get(tile).is_some()
+    }
+
+    /// Puts `value` at `tile`, returning what was there before
+    pub fn insert(&mut self, tile: IVec2, value: T) -> Option<T> {
+        let pos = chunk_pos(tile);
+        let chunk = self.chunks.entry(pos).or_insert_with(|| Chunk::new(pos));
+        let old = chunk.tiles[tile_index(tile)].replace(value);
+        if old.is_none() {
+            chunk.len += 1;
+            self.len += 1;
+        }
+        old
+    }
+
+    /// Removes the value at `tile`, freeing its chunk if it is now empty
+    pub fn remove(&mut self, tile: IVec2) -> Option<T> {
+        let pos = chunk_pos(tile);
+        let chunk = self.chunks.get_mut(&pos)?;
+        let old = chunk.tiles[tile_index(tile)].take()?;
+        chunk.len -= 1;
+        self.len -= 1;
+        if chunk.is_empty() {
+            self.chunks.remove(&pos);
+        }
+        Some(old)
+    }
+
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+        self.len = 0;
+    }
+
+    /// Returns the chunk at `pos`, in chunks, if it has any tiles
+    #[must_use]
+    pub fn chunk(&self, pos: IVec2) -> Option<&Chunk<T>> {
+        self.chunks.get(&pos)
+    }
+
+    /// Iterates over every chunk with at least one tile, in no particular
+    /// order
+    pub fn chunks(&self) -> impl Iterator<Item = &Chunk<T>> {
+        self.chunks.values()
+    }
+
+    /// Iterates over the chunks overlapping the tiles from `min` to `max`,
+    /// inclusive
+    pub fn chunks_in(&self, min: IVec2, max: IVec2) -> impl Iterator<Item = &Chunk<T>> {
+        let (min, max) = (chunk_pos(min), chunk_pos(max));
+        (min.y..=max.y)
+            .flat_map(move |y| (min.x..=max.x).map(move |x| IVec2::new(x, y)))
+            .filter_map(|pos| self.chunks.get(&pos))
+    }
+
+    /// Iterates over every tile and its position, one chunk at a time
+    pub fn iter(&self) -> impl Iterator<Item = (IVec2, &T)> {
+        self.chunks().flat_map(Chunk::iter)
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (IVec2, &mut T)> {
+        self.chunks.values_mut().flat_map(Chunk::iter_mut)
+    }
+}
@@ -0,0 +1,234 @@
+use super::{ChunkMap, Machine, MachineStatus, MachineType, Progress, Tile};
+use crate::{items::Item, prelude::*};
+use bevy::prelude::*;
+
+/// Takes an item into each of its tiles through the edges of the machine
+/// apart from its front, and combines them into one, which leaves from the
+/// front of the tile it was placed on
+#[derive(Debug)]
+pub struct Combiner {
+    machine: MachineType,
+    facing: Side,
+    /// The input on each tile, in the order given by [`super::Footprint::tiles`]
+    inputs: Vec<Option<Item>>,
+    /// How many ticks have been spent combining the current inputs
+    progress: u32,
+    /// Whether an item was in the way last time this tried to output
+    is_blocked: bool,
+    entity: Entity,
+    /// Background of the progress bar, whose child is the bar itself
+    progress_bar: Entity,
+}
+
+/// What is in a combiner, apart from the machine itself
+#[derive(Debug, Clone)]
+pub(super) struct CombinerBuffers {
+    inputs: Vec<Option<Item>>,
+    progress: u32,
+    is_blocked: bool,
+}
+
+impl From<Combiner> for Tile {
+    fn from(f: Combiner) -> Self {
+        Tile::Combiner(Box::new(f))
+    }
+}
+
+impl Combiner {
+    /// An empty combiner of one of the combiner machine types
+    pub(super) fn new(
+        machine: MachineType,
+        facing: Side,
+        entity: Entity,
+        progress_bar: Entity,
+    ) -> Self {
+        Combiner {
+            machine,
+            facing,
+            inputs: vec![None; machine.footprint().cells().count()],
+            progress: 0,
+            is_blocked: false,
+            entity,
+            progress_bar,
+        }
+    }
+
+    /// The side combined items leave from
+    #[must_use]
+    pub fn output_side(&self) -> Side {
+        self.facing
+    }
+
+    /// Puts an item moving towards `travel` into an input, giving it back if
+    /// the input is full or the item isn't coming in through one of the
+    /// input's sides
+    pub fn try_insert(&mut self, input: usize, travel: Side, item: Item) -> Result<(), Item> {
+        let entered = travel.opposite();
+        if !self
+            .machine
+            .footprint()
+            .input_sides(input, self.facing)
+            .any(|side| side == entered)
+        {
+            return Err(item);
+        }
+        match &mut self.inputs[input] {
+            slot @ None => {
+                *slot = Some(item);
+                Ok(())
+            }
+            Some(_) => Err(item),
+        }
+    }
+
+    /// The items in each input
+    #[must_use]
+    pub fn inputs(&self) -> &[Option<Item>] {
+        &self.inputs
+    }
+
+    /// How many ticks combining the inputs takes, once every input is full
+    #[must_use]
+    pub fn duration(&self) -> Option<u32> {
+        self.inputs
+            .iter()
+            .map(|input| input.as_ref().map(Item::combine_ticks))
+            .sum()
+    }
+
+    /// Works on combining the inputs for a tick, if every input is full
+    pub fn tick(&mut self) {
+        if let Some(duration) = self.duration() {
+            self.progress = (self.progress + 1).min(duration);
+        }
+    }
+
+    /// Returns the item this will output once it has finished combining
+    /// its inputs
+    #[must_use]
+    pub fn output(&self) -> Option<Item> {
+        self.duration()
+            .filter(|&duration| self.progress >= duration)?;
+        Some(Item::combine(self.inputs.iter().flatten().cloned()))
+    }
+
+    fn clear_inputs(&mut self) {
+        self.inputs.fill(None);
+        self.progress = 0;
+    }
+
+    pub(super) fn buffers(&self) -> CombinerBuffers {
+        CombinerBuffers {
+            inputs: self.inputs.clone(),
+            progress: self.progress,
+            is_blocked: self.is_blocked,
+        }
+    }
+
+    /// Puts back what was in the combiner, or empties it if it had a
+    /// different number of inputs
+    pub(super) fn set_buffers(&mut self, buffers: Option<&CombinerBuffers>) {
+        match buffers.filter(|b| b.inputs.len() == self.inputs.len()) {
+            Some(buffers) => {
+                self.inputs.clone_from(&buffers.inputs);
+                self.progress = buffers.progress;
+                self.is_blocked = buffers.is_blocked;
+            }
+            None => {
+                self.clear_inputs();
+                self.is_blocked = false;
+            }
+        }
+    }
+}
+
+impl Machine for Combiner {
+    fn from_tile(tile: &Tile) -> Option<&Self> {
+        match tile {
+            Tile::Combiner(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    fn from_tile_mut(tile: &mut Tile) -> Option<&mut Self> {
+        match tile {
+            Tile::Combiner(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    fn machine_type(&self) -> MachineType {
+        self.machine
+    }
+
+    fn facing(&self) -> Side {
+        self.facing
+    }
+
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    fn status(&self) -> MachineStatus {
+        if self.inputs.iter().all(Option::is_none) {
+            MachineStatus::Idle
+        } else if self.duration().is_none() {
+            MachineStatus::WaitingForInput
+        } else if self.is_blocked {
+            MachineStatus::BlockedOutput
+        } else {
+            MachineStatus::Working
+        }
+    }
+
+    fn ready_outputs(&self) -> Vec<(Side, Item)> {
+        self.output()
+            .map(|item| (self.output_side(), item))
+            .into_iter()
+            .collect()
+    }
+
+    /// Empties every input once their combined item has gone out
+    fn finish_outputs(&mut self, output: &[bool]) {
+        self.is_blocked = !output.iter().all(|&output| output);
+        if !self.is_blocked {
+            self.clear_inputs();
+        }
+    }
+}
+
+impl Progress for Combiner {
+    /// How far through combining its inputs this is
+    fn progress(&self) -> f32 {
+        self.duration()
+            .map_or(0.0, |duration| self.progress as f32 / duration as f32)
+    }
+
+    fn progress_bar(&self) -> Entity {
+        self.progress_bar
+    }
+}
+
+/// Returns the position of the combiner covering `tile`, the index of the
+/// input on that tile and the combiner itself
+pub(super) fn combiner_in(
+    data: &mut ChunkMap<Tile>,
+    tile: IVec2,
+) -> Option<(IVec2, usize, &mut Combiner)> {
+    let pos = match data.get(tile)? {
+        Tile::Part(parent) => *parent,
+        _ => tile,
+    };
+    match data.get_mut(pos)? {
+        Tile::Combiner(c) => {
+            let input = c
+                .machine
+                .footprint()
+                .tiles(pos, c.facing)
+                .position(|t| t == tile)
+                .unwrap();
+            Some((pos, input, c))
+        }
+        _ => None,
+    }
+}
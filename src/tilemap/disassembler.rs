@@ -0,0 +1,179 @@
+use super::{Machine, MachineStatus, MachineType, Progress, Tile};
+use crate::{items::Item, prelude::*};
+use bevy::prelude::*;
+
+/// Takes in items from its back and takes them apart into the items they
+/// were combined from, which leave from its left, right and front in turn
+#[derive(Debug)]
+pub struct Disassembler {
+    facing: Side,
+    input: Option<Item>,
+    /// How many ticks have been spent taking apart the input
+    progress: u32,
+    /// Items taken apart that haven't left yet, and the side each leaves from
+    outputs: Vec<(Side, Item)>,
+    /// Whether an item was in the way last time this tried to output
+    is_blocked: bool,
+    entity: Entity,
+    /// Background of the progress bar, whose child is the bar itself
+    progress_bar: Entity,
+}
+
+/// What is in a disassembler, apart from the machine itself
+#[derive(Debug, Clone)]
+pub(super) struct DisassemblerBuffers {
+    input: Option<Item>,
+    progress: u32,
+    outputs: Vec<(Side, Item)>,
+}
+
+impl From<Disassembler> for Tile {
+    fn from(f: Disassembler) -> Self {
+        Tile::Disassembler(Box::new(f))
+    }
+}
+
+impl Disassembler {
+    /// An empty disassembler
+    pub(super) fn new(facing: Side, entity: Entity, progress_bar: Entity) -> Self {
+        Disassembler {
+            facing,
+            input: None,
+            progress: 0,
+            outputs: Vec::new(),
+            is_blocked: false,
+            entity,
+            progress_bar,
+        }
+    }
+
+    /// The sides items leave from, in the order they take turns
+    #[must_use]
+    pub fn output_sides(&self) -> [Side; 3] {
+        [
+            self.facing.rotate_left(),
+            self.facing.rotate_right(),
+            self.facing,
+        ]
+    }
+
+    /// Takes in an item going `travel`, giving it back if it didn't come in
+    /// the back, isn't a combined item or an item is already waiting
+    pub fn try_insert(&mut self, travel: Side, item: Item) -> Result<(), Item> {
+        if travel != self.facing || self.input.is_some() || item.components().is_empty() {
+            return Err(item);
+        }
+        self.input = Some(item);
+        Ok(())
+    }
+
+    /// The item waiting to be taken apart
+    #[must_use]
+    pub fn input(&self) -> Option<&Item> {
+        self.input.as_ref()
+    }
+
+    /// The items that haven't left yet, and the side each leaves from
+    #[must_use]
+    pub fn outputs(&self) -> &[(Side, Item)] {
+        &self.outputs
+    }
+
+    /// Works on the input for a tick, taking it apart once done and every
+    /// item from the last one has left
+    pub fn tick(&mut self) {
+        let Some(duration) = self.input.as_ref().map(Item::combine_ticks) else {
+            return;
+        };
+        self.progress = (self.progress + 1).min(duration);
+        if self.progress >= duration && self.outputs.is_empty() {
+            let sides = self.output_sides();
+            let input = self.input.take().unwrap();
+            self.outputs = input
+                .components()
+                .iter()
+                .enumerate()
+                .map(|(i, item)| (sides[i % sides.len()], item.clone()))
+                .collect();
+            self.progress = 0;
+        }
+    }
+
+    pub(super) fn buffers(&self) -> DisassemblerBuffers {
+        DisassemblerBuffers {
+            input: self.input.clone(),
+            progress: self.progress,
+            outputs: self.outputs.clone(),
+        }
+    }
+
+    pub(super) fn set_buffers(&mut self, buffers: Option<&DisassemblerBuffers>) {
+        self.input = buffers.and_then(|b| b.input.clone());
+        self.progress = buffers.map_or(0, |b| b.progress);
+        self.outputs = buffers.map_or_else(Vec::new, |b| b.outputs.clone());
+        self.is_blocked = false;
+    }
+}
+
+impl Machine for Disassembler {
+    fn from_tile(tile: &Tile) -> Option<&Self> {
+        match tile {
+            Tile::Disassembler(d) => Some(d),
+            _ => None,
+        }
+    }
+
+    fn from_tile_mut(tile: &mut Tile) -> Option<&mut Self> {
+        match tile {
+            Tile::Disassembler(d) => Some(d),
+            _ => None,
+        }
+    }
+
+    fn machine_type(&self) -> MachineType {
+        MachineType::Disassembler
+    }
+
+    fn facing(&self) -> Side {
+        self.facing
+    }
+
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    fn status(&self) -> MachineStatus {
+        if self.is_blocked {
+            MachineStatus::BlockedOutput
+        } else if self.input.is_some() || !self.outputs.is_empty() {
+            MachineStatus::Working
+        } else {
+            MachineStatus::Idle
+        }
+    }
+
+    fn ready_outputs(&self) -> Vec<(Side, Item)> {
+        self.outputs.clone()
+    }
+
+    /// Lets go of the items that went out, keeping the rest for next time
+    fn finish_outputs(&mut self, output: &[bool]) {
+        let mut output = output.iter();
+        self.outputs
+            .retain(|_| !output.next().copied().unwrap_or(false));
+        self.is_blocked = !self.outputs.is_empty();
+    }
+}
+
+impl Progress for Disassembler {
+    /// How far through taking apart the input this is
+    fn progress(&self) -> f32 {
+        self.input.as_ref().map_or(0.0, |input| {
+            self.progress as f32 / input.combine_ticks() as f32
+        })
+    }
+
+    fn progress_bar(&self) -> Entity {
+        self.progress_bar
+    }
+}
@@ -0,0 +1,58 @@
+use crate::prelude::*;
+use bevy::prelude::*;
+
+/// The tiles a machine covers. A machine is placed by its front right tile,
+/// and covers the tiles behind and to the left of it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Footprint {
+    /// Width and depth in tiles, when facing north
+    size: UVec2,
+}
+
+impl Footprint {
+    #[must_use]
+    pub fn new(size: UVec2) -> Self {
+        Footprint { size }
+    }
+
+    #[must_use]
+    pub fn size(self) -> UVec2 {
+        self.size
+    }
+
+    /// Offset of each tile from the placed tile when facing north, front row
+    /// first and each row from right to left
+    pub fn cells(self) -> impl Iterator<Item = IVec2> {
+        let size = self.size.as_ivec2();
+        (0..size.y).flat_map(move |y| (0..size.x).map(move |x| IVec2::new(-x, -y)))
+    }
+
+    /// Every tile covered when placed at `pos` facing `facing_side`, in the
+    /// same order as [`Footprint::cells`], so the placed tile is first
+    pub fn tiles(self, pos: IVec2, facing_side: Side) -> impl Iterator<Item = IVec2> {
+        self.cells()
+            .map(move |cell| pos + facing_side.rotate_ivec2(cell))
+    }
+
+    /// The sides of the tile at `index` in [`Footprint::tiles`] that items
+    /// can go in through when facing `facing_side`, which are the edges of
+    /// the footprint apart from its front
+    pub fn input_sides(self, index: usize, facing_side: Side) -> impl Iterator<Item = Side> {
+        let cell = self.cells().nth(index).unwrap();
+        let size = self.size.as_ivec2();
+        let is_covered = move |cell: IVec2| {
+            (1 - size.x..=0).contains(&cell.x) && (1 - size.y..=0).contains(&cell.y)
+        };
+        [Side::West, Side::East, Side::South]
+            .into_iter()
+            .filter(move |side| !is_covered(cell + side.to_ivec2()))
+            .map(move |side| facing_side.rotate_side(side))
+    }
+
+    /// Offset of the center of the footprint from the placed tile, when
+    /// facing north
+    #[must_use]
+    pub fn center_offset(self) -> Vec2 {
+        -(self.size.as_vec2() - Vec2::ONE) / 2.0
+    }
+}
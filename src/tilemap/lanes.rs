@@ -0,0 +1,480 @@
+use super::world_to_grid_pos;
+use crate::{items::Item, prelude::*};
+use bevy::{prelude::*, utils::HashMap};
+use std::collections::BTreeMap;
+
+/// How close together items on a belt can be, in tiles
+pub const ITEM_SPACING: f32 = 0.5;
+
+/// One belt of a [`Segment`]
+#[derive(Debug, Clone, Copy)]
+struct LaneTile {
+    pos: IVec2,
+    /// The direction items are moving when they come onto this belt, which
+    /// is different to `exit` on corners
+    entry: Side,
+    exit: Side,
+}
+
+impl LaneTile {
+    /// World position of an item `fract` of the way across this belt
+    fn item_pos(self, fract: f32) -> Vec2 {
+        let center = self.pos.as_vec2();
+        if fract < 0.5 {
+            center - self.entry.to_vec2() * (0.5 - fract)
+        } else {
+            center + self.exit.to_vec2() * (fract - 0.5)
+        }
+    }
+}
+
+/// A chain of belts, each feeding into the next, whose items are moved
+/// together
+#[derive(Debug, Clone)]
+struct Segment {
+    tiles: Vec<LaneTile>,
+    /// How far along the segment each item is, in tiles. Sorted, so the
+    /// item closest to the end is last
+    offsets: Vec<f32>,
+    /// The item at each offset
+    items: Vec<Item>,
+    /// Whether the last belt feeds into the first
+    is_loop: bool,
+    /// Corners of the smallest rectangle containing every tile
+    min: IVec2,
+    max: IVec2,
+}
+
+/// Orders segments the way they are updated: chains of belts by their first
+/// belt, and then loops by their first belt
+type SegmentKey = (bool, i32, i32);
+
+impl Segment {
+    fn new(tiles: Vec<LaneTile>, is_loop: bool) -> Self {
+        let min = tiles.iter().map(|t| t.pos).reduce(IVec2::min).unwrap();
+        let max = tiles.iter().map(|t| t.pos).reduce(IVec2::max).unwrap();
+        Segment {
+            tiles,
+            offsets: Vec::new(),
+            items: Vec::new(),
+            is_loop,
+            min,
+            max,
+        }
+    }
+
+    fn key(&self) -> SegmentKey {
+        let start = self.tiles[0].pos;
+        (self.is_loop, start.y, start.x)
+    }
+
+    fn len(&self) -> f32 {
+        self.tiles.len() as f32
+    }
+
+    /// Moves every item `distance` forward, stopping them at the end of the
+    /// segment or behind the item in front
+    fn advance(&mut self, distance: f32) {
+        let mut limit = self.len();
+        for offset in self.offsets.iter_mut().rev() {
+            *offset = offset.max((*offset + distance).min(limit));
+            limit = *offset - ITEM_SPACING;
+        }
+    }
+
+    /// Returns where an item going `travel` comes onto the belt at `index`,
+    /// or `None` if the belt faces against it
+    fn entry_offset(&self, index: usize, travel: Side) -> Option<f32> {
+        let tile = self.tiles[index];
+        if tile.exit == travel.opposite() {
+            None
+        } else if tile.entry == travel {
+            Some(index as f32)
+        } else {
+            // Items coming in from the side are put in the middle of the belt
+            Some(index as f32 + 0.5)
+        }
+    }
+
+    fn has_space(&self, offset: f32) -> bool {
+        let index = self.offsets.partition_point(|&o| o < offset);
+        let clear = |o: Option<&f32>| o.is_none_or(|o| (o - offset).abs() >= ITEM_SPACING);
+        offset <= self.len()
+            && clear(self.offsets.get(index))
+            && clear(index.checked_sub(1).and_then(|i| self.offsets.get(i)))
+    }
+
+    fn insert(&mut self, offset: f32, item: Item) {
+        let index = self.offsets.partition_point(|&o| o < offset);
+        self.offsets.insert(index, offset);
+        self.items.insert(index, item);
+    }
+
+    /// Splits an offset into the tile it is on and how far across it it is
+    fn tile_at(&self, offset: f32) -> (LaneTile, f32) {
+        let index = (offset as usize).min(self.tiles.len() - 1);
+        (self.tiles[index], offset - index as f32)
+    }
+
+    fn lane_items(&self) -> impl Iterator<Item = LaneItem> + '_ {
+        self.offsets
+            .iter()
+            .zip(self.items.iter())
+            .map(|(&offset, item)| {
+                let (tile, fract) = self.tile_at(offset);
+                LaneItem {
+                    tile: tile.pos,
+                    fract,
+                    pos: tile.item_pos(fract),
+                    item: item.clone(),
+                }
+            })
+    }
+
+    fn items(&self) -> impl Iterator<Item = (Vec2, Item)> + '_ {
+        self.offsets
+            .iter()
+            .zip(self.items.iter())
+            .map(|(&offset, item)| {
+                let (tile, fract) = self.tile_at(offset);
+                (tile.item_pos(fract), item.clone())
+            })
+    }
+}
+
+/// An item on a belt, kept by which belt it is on so it can be put back
+/// after the belts are regrouped
+#[derive(Debug, Clone)]
+pub struct LaneItem {
+    tile: IVec2,
+    /// How far across the belt the item is
+    fract: f32,
+    /// Where the item was in the world, for when its belt is gone
+    pos: Vec2,
+    item: Item,
+}
+
+/// Items on belts. Belts are grouped into segments, and each segment keeps
+/// its items in arrays of offsets rather than as entities
+#[derive(Debug, Clone, Default)]
+pub struct Lanes {
+    segments: Vec<Segment>,
+    /// Which segment each belt is in, and its index in that segment
+    by_tile: HashMap<IVec2, (usize, usize)>,
+    /// Index of each segment, in the order they are updated. This only
+    /// depends on the belts, not on the order they were placed in
+    order: BTreeMap<SegmentKey, usize>,
+}
+
+impl Lanes {
+    /// Groups belts, given as their position and facing, into segments
+    #[must_use]
+    pub fn new(belts: impl IntoIterator<Item = (IVec2, Side)>) -> Self {
+        // Sorted so the same belts always make the same segments
+        let mut belts: Vec<_> = belts.into_iter().collect();
+        belts.sort_by_key(|(pos, _)| (pos.y, pos.x));
+        let sides: HashMap<IVec2, Side> = belts.iter().copied().collect();
+
+        // Each belt continues the segment of at most one belt feeding into
+        // it, preferring the one behind it
+        let mut feeders = HashMap::<IVec2, (IVec2, u8)>::default();
+        for &(pos, side) in belts.iter() {
+            let next = pos + side.to_ivec2();
+            let Some(&next_side) = sides.get(&next) else {
+                continue;
+            };
+            let priority = if side == next_side {
+                0
+            } else if side == next_side.rotate_right() {
+                1
+            } else if side == next_side.rotate_left() {
+                2
+            } else {
+                continue;
+            };
+            if feeders.get(&next).is_none_or(|&(_, p)| priority < p) {
+                feeders.insert(next, (pos, priority));
+            }
+        }
+        let next_belts: HashMap<IVec2, IVec2> = feeders
+            .iter()
+            .map(|(&next, &(pos, _))| (pos, next))
+            .collect();
+
+        let mut lanes = Lanes::default();
+        // Segments start at belts nothing feeds into, and any belts left
+        // after that are in loops
+        let starts = belts
+            .iter()
+            .filter(|(pos, _)| !feeders.contains_key(pos))
+            .chain(belts.iter());
+        for &(start, side) in starts {
+            if lanes.by_tile.contains_key(&start) {
+                continue;
+            }
+            let index = lanes.segments.len();
+            let mut tiles = Vec::new();
+            let mut pos = start;
+            let mut entry = feeders
+                .get(&start)
+                .map_or(side, |(feeder, _)| sides[feeder]);
+            loop {
+                let exit = sides[&pos];
+                lanes.by_tile.insert(pos, (index, tiles.len()));
+                tiles.push(LaneTile { pos, entry, exit });
+                match next_belts.get(&pos) {
+                    Some(&next) if !lanes.by_tile.contains_key(&next) => {
+                        entry = exit;
+                        pos = next;
+                    }
+                    _ => break,
+                }
+            }
+            let segment = Segment::new(tiles, feeders.contains_key(&start));
+            lanes.order.insert(segment.key(), index);
+            lanes.segments.push(segment);
+        }
+        lanes
+    }
+
+    /// Regroups the belts after some have been added or removed, keeping
+    /// the items on them. Returns the position of every item that was on a
+    /// belt that is no longer there
+    pub fn rebuild(&mut self, belts: impl IntoIterator<Item = (IVec2, Side)>) -> Vec<(Vec2, Item)> {
+        let items = self.lane_items();
+        *self = Lanes::new(belts);
+        self.set_items(&items)
+    }
+
+    /// Adds a belt facing `side`, only regrouping the segments next to it
+    pub fn add_belt(&mut self, pos: IVec2, side: Side) {
+        self.regroup(pos, Some(side));
+    }
+
+    /// Removes the belt at `pos`, only regrouping the segments next to it.
+    /// Returns the position of every item that was on it
+    pub fn remove_belt(&mut self, pos: IVec2) -> Vec<(Vec2, Item)> {
+        self.regroup(pos, None)
+    }
+
+    /// The side the belt at `tile` faces
+    fn side(&self, tile: IVec2) -> Option<Side> {
+        let &(s, index) = self.by_tile.get(&tile)?;
+        Some(self.segments[s].tiles[index].exit)
+    }
+
+    /// The belts facing `tile`
+    fn feeding(&self, tile: IVec2) -> impl Iterator<Item = IVec2> + '_ {
+        [Side::North, Side::East, Side::South, Side::West]
+            .into_iter()
+            .filter_map(move |from| {
+                let feeder = tile + from.to_ivec2();
+                (self.side(feeder)? == from.opposite()).then_some(feeder)
+            })
+    }
+
+    /// Replaces the belt at `pos` with one facing `side`, or nothing, and
+    /// regroups the segments that changes, as [`Lanes::new`] would.
+    ///
+    /// Only which belt feeds into `pos` and into the belt `pos` faces can
+    /// change, so only segments with those belts or belts that could feed
+    /// them are regrouped
+    fn regroup(&mut self, pos: IVec2, side: Option<Side>) -> Vec<(Vec2, Item)> {
+        let mut touched = vec![pos];
+        touched.extend(self.feeding(pos));
+        for next in [self.side(pos), side].into_iter().flatten() {
+            let next = pos + next.to_ivec2();
+            touched.push(next);
+            touched.extend(self.feeding(next));
+        }
+        let mut affected: Vec<_> = touched
+            .iter()
+            .filter_map(|tile| Some(self.by_tile.get(tile)?.0))
+            .collect();
+        affected.sort_unstable();
+        affected.dedup();
+
+        let mut belts = Vec::new();
+        let mut items = Vec::new();
+        // Highest first, so removing a segment doesn't move one still to go
+        for &s in affected.iter().rev() {
+            let segment = self.remove_segment(s);
+            items.extend(segment.lane_items());
+            belts.extend(segment.tiles.iter().map(|tile| (tile.pos, tile.exit)));
+        }
+        belts.retain(|&(tile, _)| tile != pos);
+        belts.extend(side.map(|side| (pos, side)));
+
+        for segment in Lanes::new(belts).segments {
+            let index = self.segments.len();
+            for (i, tile) in segment.tiles.iter().enumerate() {
+                self.by_tile.insert(tile.pos, (index, i));
+            }
+            self.order.insert(segment.key(), index);
+            self.segments.push(segment);
+        }
+        items
+            .iter()
+            .filter_map(|item| self.insert_lane_item(item))
+            .collect()
+    }
+
+    /// Takes out a segment, moving the last segment into its place
+    fn remove_segment(&mut self, s: usize) -> Segment {
+        let segment = self.segments.swap_remove(s);
+        self.order.remove(&segment.key());
+        for tile in segment.tiles.iter() {
+            self.by_tile.remove(&tile.pos);
+        }
+        if let Some(moved) = self.segments.get(s) {
+            for (i, tile) in moved.tiles.iter().enumerate() {
+                self.by_tile.insert(tile.pos, (s, i));
+            }
+            self.order.insert(moved.key(), s);
+        }
+        segment
+    }
+
+    /// Puts an item back on its belt, or returns where it was if the belt is
+    /// no longer there
+    fn insert_lane_item(&mut self, lane_item: &LaneItem) -> Option<(Vec2, Item)> {
+        match self.by_tile.get(&lane_item.tile) {
+            Some(&(s, index)) => {
+                self.segments[s].insert(index as f32 + lane_item.fract, lane_item.item.clone());
+                None
+            }
+            None => Some((lane_item.pos, lane_item.item.clone())),
+        }
+    }
+
+    /// Every item on every belt, in an order that can be given back to
+    /// [`Lanes::set_items`]
+    #[must_use]
+    pub fn lane_items(&self) -> Vec<LaneItem> {
+        self.segments.iter().flat_map(Segment::lane_items).collect()
+    }
+
+    /// Replaces the items on belts with `items`. Returns the position of
+    /// every item whose belt is no longer there
+    pub fn set_items(&mut self, items: &[LaneItem]) -> Vec<(Vec2, Item)> {
+        self.clear_items();
+        items
+            .iter()
+            .filter_map(|item| self.insert_lane_item(item))
+            .collect()
+    }
+
+    /// Moves every item `distance` along its belt, and then tries to move
+    /// items at the end of each segment onto whatever is after it.
+    ///
+    /// Items going onto another belt are moved if there is space. Otherwise
+    /// `outlet` is given the tile after the belt, the way the item is going
+    /// and the item, and returns whether it took the item
+    pub fn update(&mut self, distance: f32, mut outlet: impl FnMut(IVec2, Side, Item) -> bool) {
+        for segment in self.segments.iter_mut() {
+            segment.advance(distance);
+        }
+        for &index in self.order.values() {
+            let segment = &self.segments[index];
+            if segment.offsets.last().is_none_or(|&o| o < segment.len()) {
+                continue;
+            }
+            let item = segment.items.last().unwrap().clone();
+            let end = *segment.tiles.last().unwrap();
+            let target = end.pos + end.exit.to_ivec2();
+            let moved = match self.by_tile.get(&target) {
+                Some(&(s, i)) => {
+                    let offset = self.segments[s].entry_offset(i, end.exit);
+                    match offset.filter(|&o| self.segments[s].has_space(o)) {
+                        Some(offset) => {
+                            self.segments[s].insert(offset, item);
+                            true
+                        }
+                        None => false,
+                    }
+                }
+                None => outlet(target, end.exit, item),
+            };
+            if moved {
+                let segment = &mut self.segments[index];
+                segment.offsets.pop();
+                segment.items.pop();
+            }
+        }
+    }
+
+    /// Puts an item going `travel` onto the belt at `tile`, if there is space
+    pub fn try_insert(&mut self, tile: IVec2, travel: Side, item: Item) -> Result<(), Item> {
+        let Some(&(s, index)) = self.by_tile.get(&tile) else {
+            return Err(item);
+        };
+        let segment = &mut self.segments[s];
+        match segment.entry_offset(index, travel) {
+            Some(offset) if segment.has_space(offset) => {
+                segment.insert(offset, item);
+                Ok(())
+            }
+            _ => Err(item),
+        }
+    }
+
+    /// Puts an item onto the belt under `pos`, as close to `pos` as it can,
+    /// if there is space
+    pub fn try_insert_at(&mut self, pos: Vec2, item: Item) -> Result<(), Item> {
+        let tile = world_to_grid_pos(pos).tile;
+        let Some(&(s, index)) = self.by_tile.get(&tile) else {
+            return Err(item);
+        };
+        let segment = &mut self.segments[s];
+        let exit = segment.tiles[index].exit.to_vec2();
+        let fract = ((pos - tile.as_vec2()).dot(exit) + 0.5).clamp(0.0, 1.0);
+        let offset = index as f32 + fract;
+        if segment.has_space(offset) {
+            segment.insert(offset, item);
+            Ok(())
+        } else {
+            Err(item)
+        }
+    }
+
+    /// How many items are on the belt at `tile`
+    #[must_use]
+    pub fn items_on(&self, tile: IVec2) -> usize {
+        self.by_tile.get(&tile).map_or(0, |&(s, index)| {
+            let range = index as f32..(index + 1) as f32;
+            let segment = &self.segments[s];
+            // The end of the segment counts as being on its last belt
+            segment
+                .offsets
+                .iter()
+                .filter(|&&o| {
+                    range.contains(&o) || (index + 1 == segment.tiles.len() && o == range.end)
+                })
+                .count()
+        })
+    }
+
+    /// How many items are on every belt
+    #[must_use]
+    pub fn item_count(&self) -> usize {
+        self.segments.iter().map(|s| s.items.len()).sum()
+    }
+
+    /// Iterates over the items on belts in the rectangle from `min` to
+    /// `max`, and their positions. Can include items just outside it
+    pub fn items_in(&self, min: Vec2, max: Vec2) -> impl Iterator<Item = (Vec2, Item)> + '_ {
+        let (min, max) = (min.floor().as_ivec2(), max.ceil().as_ivec2());
+        self.segments
+            .iter()
+            .filter(move |s| s.min.cmple(max).all() && s.max.cmpge(min).all())
+            .flat_map(Segment::items)
+    }
+
+    /// Removes every item from every belt
+    pub fn clear_items(&mut self) {
+        for segment in self.segments.iter_mut() {
+            segment.offsets.clear();
+            segment.items.clear();
+        }
+    }
+}
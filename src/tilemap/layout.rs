@@ -0,0 +1,22 @@
+use super::MachineType;
+use crate::{items::Item, prelude::*};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Every machine the player has placed, enough to rebuild a [`super::Tilemap`]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Layout {
+    pub machines: Vec<PlacedMachine>,
+}
+
+/// A machine as placed by the player
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlacedMachine {
+    /// The grid position the machine was placed at
+    pub pos: IVec2,
+    pub machine: MachineType,
+    pub facing: Side,
+    /// The item a sorter sends out of its side
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<Item>,
+}
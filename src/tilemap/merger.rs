@@ -0,0 +1,152 @@
+use super::{Machine, MachineStatus, MachineType, Tile};
+use crate::{items::Item, prelude::*};
+use bevy::prelude::*;
+
+/// Takes items in from its back and both sides, and sends them out of its
+/// front one at a time, taking turns between the inputs
+#[derive(Debug)]
+pub struct Merger {
+    facing: Side,
+    /// The item waiting in each input, in the order given by
+    /// [`Merger::input_sides`]
+    inputs: [Option<Item>; 3],
+    /// The input whose turn it is to go out next
+    next: usize,
+    /// Whether an item was in the way last time this tried to output
+    is_blocked: bool,
+    entity: Entity,
+}
+
+/// What is in a merger and whose turn it is, apart from the machine itself
+pub(super) type MergerBuffers = ([Option<Item>; 3], usize);
+
+impl From<Merger> for Tile {
+    fn from(f: Merger) -> Self {
+        Tile::Merger(Box::new(f))
+    }
+}
+
+impl Merger {
+    /// An empty merger, whose back input goes first
+    pub(super) fn new(facing: Side, entity: Entity) -> Self {
+        Merger {
+            facing,
+            inputs: Default::default(),
+            next: 0,
+            is_blocked: false,
+            entity,
+        }
+    }
+
+    /// The side items leave from
+    #[must_use]
+    pub fn output_side(&self) -> Side {
+        self.facing
+    }
+
+    /// The sides items come in from, in the order they take turns
+    #[must_use]
+    pub fn input_sides(&self) -> [Side; 3] {
+        [
+            self.facing.rotate_left(),
+            self.facing.opposite(),
+            self.facing.rotate_right(),
+        ]
+    }
+
+    /// The items waiting in each input
+    #[must_use]
+    pub fn inputs(&self) -> &[Option<Item>; 3] {
+        &self.inputs
+    }
+
+    /// Takes in an item going `travel`, giving it back if it didn't come in
+    /// through an input or that input is already full
+    pub fn try_insert(&mut self, travel: Side, item: Item) -> Result<(), Item> {
+        let from = travel.opposite();
+        let Some(input) = self.input_sides().iter().position(|&side| side == from) else {
+            return Err(item);
+        };
+        match self.inputs[input] {
+            Some(_) => Err(item),
+            None => {
+                self.inputs[input] = Some(item);
+                Ok(())
+            }
+        }
+    }
+
+    /// The input whose item goes out next and that item. Inputs take turns,
+    /// skipping any that are empty
+    #[must_use]
+    pub fn next_output(&self) -> Option<(usize, Item)> {
+        (0..self.inputs.len())
+            .map(|i| (self.next + i) % self.inputs.len())
+            .find_map(|input| Some((input, self.inputs[input].clone()?)))
+    }
+
+    pub(super) fn buffers(&self) -> MergerBuffers {
+        (self.inputs.clone(), self.next)
+    }
+
+    pub(super) fn set_buffers(&mut self, buffers: Option<&MergerBuffers>) {
+        (self.inputs, self.next) = buffers.cloned().unwrap_or_default();
+        self.is_blocked = false;
+    }
+}
+
+impl Machine for Merger {
+    fn from_tile(tile: &Tile) -> Option<&Self> {
+        match tile {
+            Tile::Merger(m) => Some(m),
+            _ => None,
+        }
+    }
+
+    fn from_tile_mut(tile: &mut Tile) -> Option<&mut Self> {
+        match tile {
+            Tile::Merger(m) => Some(m),
+            _ => None,
+        }
+    }
+
+    fn machine_type(&self) -> MachineType {
+        MachineType::Merger
+    }
+
+    fn facing(&self) -> Side {
+        self.facing
+    }
+
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    fn status(&self) -> MachineStatus {
+        match self.next_output() {
+            None => MachineStatus::Idle,
+            Some(_) if self.is_blocked => MachineStatus::BlockedOutput,
+            Some(_) => MachineStatus::Working,
+        }
+    }
+
+    fn ready_outputs(&self) -> Vec<(Side, Item)> {
+        self.next_output()
+            .map(|(_, item)| (self.output_side(), item))
+            .into_iter()
+            .collect()
+    }
+
+    /// Empties the input whose item went out, passing the turn to the input
+    /// after it
+    fn finish_outputs(&mut self, output: &[bool]) {
+        self.is_blocked = !output.iter().all(|&output| output);
+        if self.is_blocked {
+            return;
+        }
+        if let Some((input, _)) = self.next_output() {
+            self.inputs[input] = None;
+            self.next = (input + 1) % self.inputs.len();
+        }
+    }
+}
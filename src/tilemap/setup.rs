@@ -1,6 +1,6 @@
 use super::{TextureMap, Tilemap};
 use crate::prelude::*;
-use bevy::{asset::LoadState, prelude::*, utils::HashMap};
+use bevy::{asset::LoadState, prelude::*};
 
 pub struct Plugin;
 
@@ -53,9 +53,20 @@ fn create_atlas_system(
 
             let texture_map = TextureMap {
                 delete_tool: handle_from_name("ui/delete.png"),
+                inspect_tool: handle_from_name("ui/inspect.png"),
                 belt: handle_from_name("tiles/belt_0.png"),
                 ice: handle_from_name("tiles/ice.png"),
                 combiner2x1: handle_from_name("tiles/combiner2x1.png"),
+                combiner3x1: handle_from_name("tiles/combiner3x1.png"),
+                combiner2x2: handle_from_name("tiles/combiner2x2.png"),
+                sorter: handle_from_name("tiles/sorter.png"),
+                merger: handle_from_name("tiles/merger.png"),
+                disassembler: handle_from_name("tiles/disassembler.png"),
+                wall: handle_from_name("tiles/wall.png"),
+                void: handle_from_name("tiles/void.png"),
+                fixed_ice: handle_from_name("tiles/ice_fixed.png"),
+                target: handle_from_name("tiles/target.png"),
+                source: handle_from_name("tiles/source.png"),
                 item_a: handle_from_name("items/a.png"),
                 item_b: handle_from_name("items/b.png"),
                 item_c: handle_from_name("items/c.png"),
@@ -63,13 +74,10 @@ fn create_atlas_system(
                 atlas: atlases.add(atlas),
             };
 
-            commands.insert_resource(Tilemap {
-                data: HashMap::with_capacity(super::MIN_MAP_SIZE),
-                textures: texture_map,
-            });
+            commands.insert_resource(Tilemap::new(texture_map));
 
             commands.remove_resource::<TileTextureHandles>();
-            state.set(AppState::Game).unwrap();
+            state.set(AppState::MainMenu).unwrap();
         }
     }
 }
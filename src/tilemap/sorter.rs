@@ -0,0 +1,135 @@
+use super::{Machine, MachineStatus, MachineType, Tile};
+use crate::{items::Item, prelude::*};
+use bevy::prelude::*;
+
+/// Takes in items from its back, and sends those matching its filter out of
+/// its right side and every other item straight through
+#[derive(Debug)]
+pub struct Sorter {
+    facing: Side,
+    filter: Item,
+    /// The item waiting to go out
+    held: Option<Item>,
+    /// Whether an item was in the way last time this tried to output
+    is_blocked: bool,
+    entity: Entity,
+    /// Shows the filter on top of the machine
+    overlay: Entity,
+}
+
+impl From<Sorter> for Tile {
+    fn from(f: Sorter) -> Self {
+        Tile::Sorter(Box::new(f))
+    }
+}
+
+impl Sorter {
+    /// An empty sorter, whose filter is shown by `overlay`
+    pub(super) fn new(facing: Side, filter: Item, entity: Entity, overlay: Entity) -> Self {
+        Sorter {
+            facing,
+            filter,
+            held: None,
+            is_blocked: false,
+            entity,
+            overlay,
+        }
+    }
+
+    /// The item sent out of the right side
+    #[must_use]
+    pub fn filter(&self) -> &Item {
+        &self.filter
+    }
+
+    pub fn set_filter(&mut self, filter: Item) {
+        self.filter = filter;
+    }
+
+    /// The item waiting to go out
+    #[must_use]
+    pub fn held(&self) -> Option<&Item> {
+        self.held.as_ref()
+    }
+
+    /// The side `item` leaves from
+    #[must_use]
+    pub fn output_side(&self, item: &Item) -> Side {
+        if *item == self.filter {
+            self.facing.rotate_right()
+        } else {
+            self.facing
+        }
+    }
+
+    /// Takes in an item going `travel`, giving it back if it didn't come in
+    /// the back or one is already waiting to go out
+    pub fn try_insert(&mut self, travel: Side, item: Item) -> Result<(), Item> {
+        if travel != self.facing || self.held.is_some() {
+            return Err(item);
+        }
+        self.held = Some(item);
+        Ok(())
+    }
+
+    /// Shows the filter on top of the machine
+    pub(super) fn overlay(&self) -> Entity {
+        self.overlay
+    }
+
+    /// Puts back the item that was waiting to go out
+    pub(super) fn set_held(&mut self, held: Option<Item>) {
+        self.held = held;
+        self.is_blocked = false;
+    }
+}
+
+impl Machine for Sorter {
+    fn from_tile(tile: &Tile) -> Option<&Self> {
+        match tile {
+            Tile::Sorter(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn from_tile_mut(tile: &mut Tile) -> Option<&mut Self> {
+        match tile {
+            Tile::Sorter(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn machine_type(&self) -> MachineType {
+        MachineType::Sorter
+    }
+
+    fn facing(&self) -> Side {
+        self.facing
+    }
+
+    fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    fn status(&self) -> MachineStatus {
+        match self.held {
+            None => MachineStatus::Idle,
+            Some(_) if self.is_blocked => MachineStatus::BlockedOutput,
+            Some(_) => MachineStatus::Working,
+        }
+    }
+
+    fn ready_outputs(&self) -> Vec<(Side, Item)> {
+        self.held
+            .iter()
+            .map(|item| (self.output_side(item), item.clone()))
+            .collect()
+    }
+
+    fn finish_outputs(&mut self, output: &[bool]) {
+        self.is_blocked = !output.iter().all(|&output| output);
+        if !self.is_blocked {
+            self.held = None;
+        }
+    }
+}
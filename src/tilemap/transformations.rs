@@ -1,5 +1,6 @@
 use crate::prelude::*;
-use bevy::prelude::*;
+use bevy::{prelude::*, render::camera::CameraProjection};
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Copy)]
 pub struct ScreenToWorldInputs<'a> {
@@ -17,21 +18,27 @@ pub fn screen_to_world_pos(inputs: ScreenToWorldInputs, screen_pos: Vec2) -> Vec
         camera_transform,
     } = inputs;
 
-    let window_size = Vec2::new(window.width() as f32, window.height() as f32);
+    let window_size = Vec2::new(window.width(), window.height());
     let ndc = (screen_pos / window_size) * 2.0 - Vec2::ONE;
     let ndc_to_world = camera_transform.compute_matrix() * camera.projection_matrix().inverse();
     ndc_to_world.project_point3(ndc.extend(-1.0)).truncate()
 }
 
-#[derive(Debug, Clone, Copy)]
-pub struct CursorToWorldInputs<'a> {
-    pub windows: &'a Windows,
-    pub camera: &'a Camera,
-    pub camera_transform: &'a GlobalTransform,
+/// Returns the bottom left and top right corners of what the camera can see,
+/// in world space. Uses the camera's projection rather than its [`Camera`],
+/// whose matrix is only set once there is a window to draw to
+pub fn camera_view_rect(
+    projection: &OrthographicProjection,
+    camera_transform: &GlobalTransform,
+) -> (Vec2, Vec2) {
+    let ndc_to_world =
+        camera_transform.compute_matrix() * projection.get_projection_matrix().inverse();
+    let corner = |ndc: Vec2| ndc_to_world.project_point3(ndc.extend(-1.0)).truncate();
+    (corner(-Vec2::ONE), corner(Vec2::ONE))
 }
 
 /// A position on the grid calculated from a position in world space
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct GridPos {
     /// The tile the position is over
     pub tile: IVec2,
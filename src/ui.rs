@@ -0,0 +1,124 @@
+use bevy::{ecs::system::EntityCommands, prelude::*};
+
+pub struct Plugin;
+
+impl bevy::prelude::Plugin for Plugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(startup_system)
+            .add_system(button_color_system);
+    }
+}
+
+/// The font used for all UI text
+#[derive(Debug)]
+pub struct UiFont(pub Handle<Font>);
+
+pub const TEXT_COLOR: Color = Color::WHITE;
+pub const DISABLED_TEXT_COLOR: Color = Color::rgb(0.4, 0.4, 0.4);
+
+const BUTTON_COLOR: Color = Color::rgb(0.15, 0.15, 0.15);
+const BUTTON_HOVERED_COLOR: Color = Color::rgb(0.25, 0.25, 0.25);
+const BUTTON_CLICKED_COLOR: Color = Color::rgb(0.35, 0.35, 0.35);
+const BUTTON_DISABLED_COLOR: Color = Color::rgb(0.08, 0.08, 0.08);
+
+/// UI that the mouse can't use tools through, so clicking it doesn't also
+/// change the tile underneath
+#[derive(Debug, Component)]
+pub struct BlocksWorldInput;
+
+/// A button spawned with [`spawn_text_button`], colored by its `Interaction`
+#[derive(Debug, Component)]
+pub struct TextButton;
+
+fn startup_system(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(UiFont(asset_server.load("fonts/DejaVuSans.ttf")));
+}
+
+impl UiFont {
+    /// Returns a text section style using this font
+    #[must_use]
+    pub fn style(&self, font_size: f32, color: Color) -> TextStyle {
+        TextStyle {
+            font: self.0.clone(),
+            font_size,
+            color,
+        }
+    }
+
+    /// Returns a bundle for a single line of text using this font
+    #[must_use]
+    pub fn text(&self, value: impl Into<String>, font_size: f32, color: Color) -> TextBundle {
+        TextBundle::from_section(value, self.style(font_size, color))
+    }
+}
+
+/// Spawns a button with a text label as a child of `parent`.
+/// Disabled buttons are drawn greyed out and never receive an `Interaction`
+pub fn spawn_text_button<'w, 's, 'a>(
+    parent: &'a mut ChildBuilder<'w, 's, '_>,
+    font: &UiFont,
+    label: &str,
+    enabled: bool,
+) -> EntityCommands<'w, 's, 'a> {
+    spawn_sized_text_button(parent, font, label, enabled, Vec2::new(260.0, 44.0), 24.0)
+}
+
+/// Spawns a smaller version of [`spawn_text_button`], for use in lists
+pub fn spawn_small_text_button<'w, 's, 'a>(
+    parent: &'a mut ChildBuilder<'w, 's, '_>,
+    font: &UiFont,
+    label: &str,
+) -> EntityCommands<'w, 's, 'a> {
+    spawn_sized_text_button(parent, font, label, true, Vec2::new(150.0, 30.0), 18.0)
+}
+
+fn spawn_sized_text_button<'w, 's, 'a>(
+    parent: &'a mut ChildBuilder<'w, 's, '_>,
+    font: &UiFont,
+    label: &str,
+    enabled: bool,
+    size: Vec2,
+    font_size: f32,
+) -> EntityCommands<'w, 's, 'a> {
+    let style = Style {
+        size: Size::new(Val::Px(size.x), Val::Px(size.y)),
+        margin: UiRect::all(Val::Px(size.y * 0.125)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let mut button = if enabled {
+        parent.spawn_bundle(ButtonBundle {
+            style,
+            color: BUTTON_COLOR.into(),
+            ..default()
+        })
+    } else {
+        parent.spawn_bundle(NodeBundle {
+            style,
+            color: BUTTON_DISABLED_COLOR.into(),
+            ..default()
+        })
+    };
+    let text_color = if enabled {
+        TEXT_COLOR
+    } else {
+        DISABLED_TEXT_COLOR
+    };
+    button.insert(TextButton).with_children(|button| {
+        button.spawn_bundle(font.text(label, font_size, text_color));
+    });
+    button
+}
+
+fn button_color_system(
+    mut button_query: Query<(&Interaction, &mut UiColor), (With<TextButton>, Changed<Interaction>)>,
+) {
+    for (interaction, mut color) in button_query.iter_mut() {
+        color.0 = match interaction {
+            Interaction::Clicked => BUTTON_CLICKED_COLOR,
+            Interaction::Hovered => BUTTON_HOVERED_COLOR,
+            Interaction::None => BUTTON_COLOR,
+        };
+    }
+}
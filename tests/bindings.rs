@@ -0,0 +1,64 @@
+use bevy::prelude::{KeyCode, MouseButton};
+use multifactory::bindings::{Action, Binding, Bindings};
+
+/// Bindings saved before tools had hotkeys and before remove had its own
+/// action, with spawning items moved from the number keys to F5 by the player
+const OLD_BINDINGS: &str = "{
+    UseTool: (Some(Mouse(Left)), Some(Mouse(Right))),
+    RotateLeft: (Some(Key(A)), Some(Key(Left))),
+    RotateRight: (Some(Key(D)), Some(Key(Right))),
+    SpawnItemA: (Some(Key(Key1)), None),
+    SpawnItemB: (Some(Key(Key2)), None),
+    SpawnItemC: (Some(Key(F5)), None),
+    SpawnItemD: (Some(Key(Key4)), Some(Key(Key5))),
+    ClearItems: (Some(Key(LShift)), None),
+    Back: (Some(Key(Escape)), None),
+}";
+
+#[test]
+fn old_defaults_make_way_for_new_actions() {
+    let bindings = Bindings::from_ron(OLD_BINDINGS).unwrap();
+    let key = |key| Some(Binding::Key(key));
+    assert_eq!(
+        bindings.get(Action::UseTool),
+        [Some(Binding::Mouse(MouseButton::Left)), None]
+    );
+    assert_eq!(
+        bindings.get(Action::Remove),
+        [Some(Binding::Mouse(MouseButton::Right)), None]
+    );
+    assert_eq!(
+        bindings.get(Action::SelectTool(0)),
+        [key(KeyCode::Key1), None]
+    );
+    assert_eq!(bindings.get(Action::SpawnItemA), [key(KeyCode::F1), None]);
+    assert_eq!(bindings.get(Action::SpawnItemB), [key(KeyCode::F2), None]);
+    assert_eq!(
+        bindings.get(Action::SpawnItemD),
+        [key(KeyCode::F4), key(KeyCode::Key5)]
+    );
+}
+
+#[test]
+fn chosen_bindings_are_kept() {
+    let bindings = Bindings::from_ron(OLD_BINDINGS).unwrap();
+    assert_eq!(
+        bindings.get(Action::SpawnItemC),
+        [Some(Binding::Key(KeyCode::F5)), None]
+    );
+    assert_eq!(bindings.get(Action::SelectTool(4)), [None, None]);
+    assert_eq!(bindings.get(Action::Save), [None, None]);
+}
+
+#[test]
+fn no_binding_is_used_twice() {
+    let bindings = Bindings::from_ron(OLD_BINDINGS).unwrap();
+    let mut used: Vec<Binding> = Action::all()
+        .flat_map(|action| bindings.get(action))
+        .flatten()
+        .collect();
+    let count = used.len();
+    used.sort_by_key(|binding| format!("{binding:?}"));
+    used.dedup();
+    assert_eq!(used.len(), count);
+}
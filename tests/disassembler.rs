@@ -0,0 +1,37 @@
+use multifactory::{headless, items::Item};
+
+/// A combiner joining A and B, whose output a disassembler takes apart
+/// again onto targets on either side of it
+const LEVEL: &str = r#"(
+    name: "Disassembler",
+    description: "",
+    terrain: [
+        (terrain: Source(A), min: (-3, 0), max: (-3, 0)),
+        (terrain: Source(B), min: (-3, 1), max: (-3, 1)),
+        (terrain: Target, min: (2, -1), max: (2, -1)),
+        (terrain: Target, min: (2, 1), max: (2, 1)),
+    ],
+)"#;
+
+const LAYOUT: &str = r#"(
+    machines: [
+        (pos: (-2, 0), machine: Belt, facing: East),
+        (pos: (-1, 0), machine: Belt, facing: East),
+        (pos: (-2, 1), machine: Belt, facing: East),
+        (pos: (-1, 1), machine: Belt, facing: East),
+        (pos: (0, 0), machine: Combiner2x1, facing: East),
+        (pos: (1, 0), machine: Belt, facing: East),
+        (pos: (2, 0), machine: Disassembler, facing: East),
+    ],
+)"#;
+
+const TICKS: u64 = 60 * 30;
+
+#[test]
+fn disassembler_takes_apart_combined_items() {
+    let delivered = headless::deliveries(LEVEL, LAYOUT, TICKS).unwrap();
+    let count = |wanted: Item| delivered.iter().filter(|&item| *item == wanted).count();
+    assert!(count(Item::A) >= 3, "{delivered:?}");
+    assert_eq!(count(Item::A), count(Item::B), "{delivered:?}");
+    assert_eq!(count(Item::A) + count(Item::B), delivered.len());
+}
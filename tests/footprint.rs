@@ -0,0 +1,96 @@
+use bevy::prelude::*;
+use multifactory::{direction::Side, tilemap::Footprint};
+
+const SIDES: [Side; 4] = [Side::North, Side::East, Side::South, Side::West];
+const SIZES: [UVec2; 3] = [UVec2::new(2, 1), UVec2::new(3, 1), UVec2::new(2, 2)];
+const POS: IVec2 = IVec2::new(5, -3);
+
+/// The direction to the right of `side`, worked out from vectors rather than
+/// with [`Side::rotate_right`]
+fn right_of(side: Side) -> IVec2 {
+    let v = side.to_ivec2();
+    IVec2::new(v.y, -v.x)
+}
+
+fn sorted(mut sides: Vec<Side>) -> Vec<Side> {
+    sides.sort_by_key(|side| *side as u8);
+    sides
+}
+
+#[test]
+fn tiles_are_behind_and_left_of_the_placed_tile() {
+    for size in SIZES {
+        let footprint = Footprint::new(size);
+        for facing in SIDES {
+            let tiles: Vec<_> = footprint.tiles(POS, facing).collect();
+            assert_eq!(tiles.len(), (size.x * size.y) as usize);
+            assert_eq!(tiles[0], POS, "{size} facing {facing:?}");
+            let mut expected = Vec::new();
+            for depth in 0..size.y as i32 {
+                for left in 0..size.x as i32 {
+                    expected.push(POS - facing.to_ivec2() * depth - right_of(facing) * left);
+                }
+            }
+            assert_eq!(tiles, expected, "{size} facing {facing:?}");
+        }
+    }
+}
+
+#[test]
+fn tiles_of_some_footprints() {
+    let tiles = |size, facing| {
+        Footprint::new(size)
+            .tiles(IVec2::ZERO, facing)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        tiles(UVec2::new(2, 1), Side::East),
+        [IVec2::new(0, 0), IVec2::new(0, 1)]
+    );
+    assert_eq!(
+        tiles(UVec2::new(3, 1), Side::South),
+        [IVec2::new(0, 0), IVec2::new(1, 0), IVec2::new(2, 0)]
+    );
+    assert_eq!(
+        tiles(UVec2::new(2, 2), Side::West),
+        [
+            IVec2::new(0, 0),
+            IVec2::new(0, -1),
+            IVec2::new(1, 0),
+            IVec2::new(1, -1),
+        ]
+    );
+}
+
+#[test]
+fn input_sides_are_outer_edges_except_the_front() {
+    for size in SIZES {
+        let footprint = Footprint::new(size);
+        for facing in SIDES {
+            let tiles: Vec<_> = footprint.tiles(POS, facing).collect();
+            for (index, tile) in tiles.iter().enumerate() {
+                let expected = SIDES
+                    .into_iter()
+                    .filter(|side| *side != facing && !tiles.contains(&(*tile + side.to_ivec2())))
+                    .collect();
+                let sides = footprint.input_sides(index, facing).collect();
+                assert_eq!(
+                    sorted(sides),
+                    sorted(expected),
+                    "tile {index} of {size} facing {facing:?}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn input_sides_of_a_two_by_two() {
+    let footprint = Footprint::new(UVec2::new(2, 2));
+    let sides = |index| sorted(footprint.input_sides(index, Side::North).collect());
+    // Front right, front left, back right, back left
+    assert_eq!(sides(0), [Side::East]);
+    assert_eq!(sides(1), [Side::West]);
+    assert_eq!(sides(2), [Side::East, Side::South]);
+    assert_eq!(sides(3), [Side::South, Side::West]);
+}
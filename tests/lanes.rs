@@ -0,0 +1,88 @@
+use bevy::{prelude::*, utils::HashMap};
+use multifactory::{direction::Side, items::Item, tilemap::Lanes};
+
+const SIDES: [Side; 4] = [Side::North, Side::East, Side::South, Side::West];
+const SIZE: i32 = 8;
+
+/// A small deterministic random number generator, so the tests are the same
+/// every run
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self, below: u64) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (self.0 >> 33) % below
+    }
+}
+
+/// Every item and where it is, in a fixed order
+fn items(lanes: &Lanes) -> Vec<(IVec2, Item)> {
+    let mut items: Vec<_> = lanes
+        .items_in(Vec2::splat(-1.0), Vec2::splat(SIZE as f32))
+        .map(|(pos, item)| ((pos * 1000.0).round().as_ivec2(), item))
+        .collect();
+    items.sort_by_key(|(pos, item)| (pos.y, pos.x, format!("{item:?}")));
+    items
+}
+
+/// Puts an item in the middle of every belt, then moves items along until
+/// most have stopped
+fn run(lanes: &mut Lanes, belts: &HashMap<IVec2, Side>) {
+    let mut tiles: Vec<_> = belts.keys().copied().collect();
+    tiles.sort_by_key(|pos| (pos.y, pos.x));
+    for tile in tiles {
+        let _ = lanes.try_insert_at(tile.as_vec2(), Item::A);
+    }
+    for _ in 0..200 {
+        // Takes items leaving some of the belts, so items keep moving
+        lanes.update(0.05, |tile, _, _| tile.x.rem_euclid(3) == 0);
+    }
+}
+
+#[test]
+fn adding_and_removing_belts_matches_grouping_from_scratch() {
+    for seed in 0..20 {
+        let mut rng = Rng(seed);
+        let mut lanes = Lanes::default();
+        let mut belts = HashMap::default();
+        for _ in 0..300 {
+            let pos = IVec2::new(rng.next(SIZE as u64) as i32, rng.next(SIZE as u64) as i32);
+            if belts.remove(&pos).is_some() {
+                lanes.remove_belt(pos);
+            } else {
+                let side = SIDES[rng.next(4) as usize];
+                belts.insert(pos, side);
+                lanes.add_belt(pos, side);
+            }
+        }
+        let mut fresh = Lanes::new(belts.iter().map(|(&pos, &side)| (pos, side)));
+        run(&mut lanes, &belts);
+        run(&mut fresh, &belts);
+        assert_eq!(items(&lanes), items(&fresh), "seed {seed}");
+    }
+}
+
+#[test]
+fn removing_a_belt_drops_only_its_items() {
+    let mut lanes = Lanes::default();
+    for x in 0..5 {
+        lanes.add_belt(IVec2::new(x, 0), Side::East);
+    }
+    for x in 0..5 {
+        lanes
+            .try_insert_at(Vec2::new(x as f32, 0.0), Item::A)
+            .unwrap();
+    }
+    let dropped = lanes.remove_belt(IVec2::new(2, 0));
+    assert_eq!(dropped, vec![(Vec2::new(2.0, 0.0), Item::A)]);
+    assert_eq!(lanes.item_count(), 4);
+    // The belts on either side are now separate, so items stop at the gap
+    for _ in 0..100 {
+        lanes.update(0.05, |_, _, _| false);
+    }
+    assert_eq!(lanes.items_on(IVec2::new(1, 0)), 2);
+    assert_eq!(lanes.items_on(IVec2::new(4, 0)), 2);
+}
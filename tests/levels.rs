@@ -0,0 +1,24 @@
+use multifactory::headless;
+use std::{fs, path::Path};
+
+/// Ten minutes of game time
+const MAX_TICKS: u64 = 60 * 60 * 10;
+
+#[test]
+fn shipped_solutions_complete_their_levels() {
+    let assets = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets");
+    let mut levels: Vec<_> = fs::read_dir(assets.join("levels"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    levels.sort();
+    assert!(!levels.is_empty());
+    for name in levels {
+        let level = fs::read_to_string(assets.join("levels").join(&name)).unwrap();
+        let solution = fs::read_to_string(assets.join("solutions").join(&name))
+            .unwrap_or_else(|e| panic!("no solution for {name:?}: {e}"));
+        let verification = headless::verify(&level, &solution, MAX_TICKS)
+            .unwrap_or_else(|e| panic!("{name:?}: {e}"));
+        assert!(verification.completed, "{name:?}: {verification:?}");
+    }
+}
@@ -0,0 +1,46 @@
+use multifactory::{headless, items::Item};
+
+/// Two sources of each item feeding one merger, from its back and its right,
+/// which is more than its output can carry so both inputs are always full
+const LEVEL: &str = r#"(
+    name: "Merger",
+    description: "",
+    terrain: [
+        (terrain: Source(A), min: (-3, 0), max: (-3, 0)),
+        (terrain: Source(A), min: (-2, 1), max: (-2, 1)),
+        (terrain: Source(B), min: (0, -3), max: (0, -3)),
+        (terrain: Source(B), min: (1, -2), max: (1, -2)),
+        (terrain: Target, min: (3, 0), max: (3, 0)),
+    ],
+)"#;
+
+const LAYOUT: &str = r#"(
+    machines: [
+        (pos: (-2, 0), machine: Belt, facing: East),
+        (pos: (-1, 0), machine: Belt, facing: East),
+        (pos: (0, -2), machine: Belt, facing: North),
+        (pos: (0, -1), machine: Belt, facing: North),
+        (pos: (0, 0), machine: Merger, facing: East),
+        (pos: (1, 0), machine: Belt, facing: East),
+        (pos: (2, 0), machine: Belt, facing: East),
+    ],
+)"#;
+
+const TICKS: u64 = 60 * 20;
+
+#[test]
+fn merger_takes_turns() {
+    let delivered = headless::deliveries(LEVEL, LAYOUT, TICKS).unwrap();
+    assert!(delivered.len() >= 10, "{delivered:?}");
+    for pair in delivered.windows(2) {
+        assert_ne!(pair[0], pair[1], "{delivered:?}");
+    }
+}
+
+#[test]
+fn merger_is_deterministic() {
+    let first = headless::deliveries(LEVEL, LAYOUT, TICKS).unwrap();
+    let second = headless::deliveries(LEVEL, LAYOUT, TICKS).unwrap();
+    assert_eq!(first, second);
+    assert!(first.contains(&Item::A) && first.contains(&Item::B));
+}
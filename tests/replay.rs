@@ -0,0 +1,143 @@
+use bevy::prelude::*;
+use multifactory::{
+    direction::Side,
+    headless,
+    replay::{Buttons, Frame, Recording, Tool},
+    tilemap::{GridPos, MachineType, PlacedMachine},
+    Click, MouseInput,
+};
+
+const LEVEL: &str = include_str!("../assets/levels/01_first_steps.ron");
+
+fn mouse_over(tile: IVec2, click: Click) -> MouseInput {
+    MouseInput {
+        pos: Some(GridPos {
+            tile,
+            hitvec: Vec2::splat(0.5),
+        }),
+        click,
+    }
+}
+
+/// Turns to face east, then drags a line of belts from the source to the
+/// target and waits for items to arrive
+fn belt_line() -> Recording {
+    let belt = Tool::Place(MachineType::Belt);
+    let mut frames = vec![
+        Frame {
+            keys: Buttons {
+                pressed: vec![KeyCode::D],
+                just_pressed: vec![KeyCode::D],
+                ..default()
+            },
+            ticks: 1,
+            ..default()
+        },
+        Frame {
+            keys: Buttons {
+                just_released: vec![KeyCode::D],
+                ..default()
+            },
+            ticks: 1,
+            ..default()
+        },
+    ];
+    frames.extend((-6..=6).map(|x| Frame {
+        ticks: 1,
+        mouse: mouse_over(IVec2::new(x, 0), Click::UseTool),
+        mouse_buttons: Buttons {
+            pressed: vec![MouseButton::Left],
+            just_pressed: if x == -6 {
+                vec![MouseButton::Left]
+            } else {
+                vec![]
+            },
+            ..default()
+        },
+        tool: belt,
+        ..default()
+    }));
+    frames.extend((0..10).map(|_| Frame {
+        ticks: 60,
+        tool: belt,
+        ..default()
+    }));
+    Recording {
+        level: Some(0),
+        frames,
+        ..default()
+    }
+}
+
+#[test]
+fn replay_places_machines() {
+    let replayed = headless::replay(Some(LEVEL), belt_line()).unwrap();
+    let expected: Vec<_> = (-6..=6)
+        .map(|x| PlacedMachine {
+            pos: IVec2::new(x, 0),
+            machine: MachineType::Belt,
+            facing: Side::East,
+            filter: None,
+        })
+        .collect();
+    let mut placed = replayed.layout.machines;
+    placed.sort_by_key(|m| m.pos.x);
+    assert_eq!(placed, expected);
+    assert_eq!(replayed.ticks, 2 + 13 + 600);
+    assert!(replayed.delivered > 0);
+}
+
+#[test]
+fn recording_with_missing_bindings_replays() {
+    // Made before most actions existed, so only turning is bound
+    let mut recording: Recording = ron::from_str(
+        "(
+            level: Some(0),
+            layout: (machines: []),
+            direction: North,
+            bindings: ({RotateRight: (Some(Key(D)), None)}),
+            frames: [],
+        )",
+    )
+    .unwrap();
+    recording.frames = belt_line().frames;
+    let replayed = headless::replay(Some(LEVEL), recording).unwrap();
+    let original = headless::replay(Some(LEVEL), belt_line()).unwrap();
+    assert_eq!(replayed.layout, original.layout);
+    assert_eq!(replayed.delivered, original.delivered);
+}
+
+#[test]
+fn replay_is_deterministic() {
+    let first = headless::replay(Some(LEVEL), belt_line()).unwrap();
+    let second = headless::replay(Some(LEVEL), belt_line()).unwrap();
+    assert_eq!(first.layout, second.layout);
+    assert_eq!(first.delivered, second.delivered);
+}
+
+#[test]
+fn recording_survives_saving() {
+    let path = std::env::temp_dir().join("multifactory-replay-test.ron");
+    belt_line().save(&path).unwrap();
+    let loaded = Recording::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let replayed = headless::replay(Some(LEVEL), loaded).unwrap();
+    let original = headless::replay(Some(LEVEL), belt_line()).unwrap();
+    assert_eq!(replayed.layout, original.layout);
+    assert_eq!(replayed.delivered, original.delivered);
+}
+
+#[test]
+fn rewind_keeps_layout() {
+    let mut recording = belt_line();
+    recording.frames.push(Frame {
+        rewind: Some(0),
+        ..default()
+    });
+    let replayed = headless::replay(Some(LEVEL), recording).unwrap();
+    let original = headless::replay(Some(LEVEL), belt_line()).unwrap();
+    assert_eq!(replayed.layout, original.layout);
+    // The first snapshot is taken after the first tick
+    assert_eq!(replayed.ticks, 1);
+    assert_eq!(replayed.delivered, 0);
+}
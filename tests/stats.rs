@@ -0,0 +1,123 @@
+use bevy::prelude::*;
+use multifactory::{
+    items::Item,
+    stats::{Counts, ItemEvent, ItemEventKind, ThroughputStats, BUCKET_TICKS, HISTORY_BUCKETS},
+};
+
+fn event(kind: ItemEventKind, item: Item, machine: Option<IVec2>) -> ItemEvent {
+    ItemEvent {
+        kind,
+        item,
+        machine,
+    }
+}
+
+/// Records `events` in the bucket starting at tick `bucket * BUCKET_TICKS`,
+/// then completes it
+fn fill_bucket(stats: &mut ThroughputStats, bucket: u64, events: &[ItemEvent]) {
+    stats.advance_to(bucket * BUCKET_TICKS);
+    for event in events {
+        stats.record(event);
+    }
+    stats.advance_to((bucket + 1) * BUCKET_TICKS);
+}
+
+fn counts(stats: &ThroughputStats, buckets: usize, item: &Item) -> Counts {
+    stats
+        .by_item(buckets)
+        .into_iter()
+        .find(|(i, _)| i == item)
+        .map(|(_, counts)| counts)
+        .unwrap_or_default()
+}
+
+#[test]
+fn only_completed_buckets_are_counted() {
+    let mut stats = ThroughputStats::default();
+    stats.record(&event(ItemEventKind::Produced, Item::A, None));
+    stats.advance_to(BUCKET_TICKS - 1);
+    assert_eq!(stats.completed_buckets(), 0);
+    assert!(stats.by_item(10).is_empty());
+    assert_eq!(stats.window_seconds(10), 0.0);
+
+    stats.advance_to(BUCKET_TICKS);
+    assert_eq!(stats.completed_buckets(), 1);
+    assert_eq!(counts(&stats, 10, &Item::A).get(ItemEventKind::Produced), 1);
+    assert_eq!(stats.window_seconds(10), 1.0);
+}
+
+#[test]
+fn windows_cover_the_newest_buckets() {
+    let mut stats = ThroughputStats::default();
+    let machine = IVec2::new(2, -1);
+    for bucket in 0..5 {
+        let events: Vec<_> = (0..=bucket)
+            .map(|_| event(ItemEventKind::Consumed, Item::B, Some(machine)))
+            .collect();
+        fill_bucket(&mut stats, bucket, &events);
+    }
+    // The newest two buckets had 5 and 4 events
+    assert_eq!(counts(&stats, 2, &Item::B).get(ItemEventKind::Consumed), 9);
+    assert_eq!(counts(&stats, 5, &Item::B).get(ItemEventKind::Consumed), 15);
+    assert_eq!(stats.window_seconds(2), 2.0);
+    assert_eq!(stats.window_seconds(100), 5.0);
+    let by_machine = stats.by_machine(2);
+    assert_eq!(by_machine.len(), 1);
+    assert_eq!(by_machine[0].0, machine);
+    assert_eq!(by_machine[0].1.total(), 9);
+}
+
+#[test]
+fn history_counts_new_items_and_is_limited() {
+    let mut stats = ThroughputStats::default();
+    let buckets = HISTORY_BUCKETS as u64 + 10;
+    for bucket in 0..buckets {
+        fill_bucket(
+            &mut stats,
+            bucket,
+            &[
+                event(ItemEventKind::Produced, Item::A, None),
+                event(ItemEventKind::Combined, Item::C, None),
+                event(ItemEventKind::Disassembled, Item::D, None),
+                // Neither makes a new item
+                event(ItemEventKind::Consumed, Item::A, None),
+                event(ItemEventKind::Delivered, Item::C, None),
+            ],
+        );
+    }
+    let history: Vec<_> = stats.history().collect();
+    assert_eq!(history, vec![3; HISTORY_BUCKETS]);
+    assert_eq!(stats.completed_buckets(), buckets);
+}
+
+#[test]
+fn rewinding_drops_later_buckets() {
+    let mut stats = ThroughputStats::default();
+    for bucket in 0..4 {
+        fill_bucket(
+            &mut stats,
+            bucket,
+            &[event(ItemEventKind::Produced, Item::A, None)],
+        );
+    }
+    stats.advance_to(2 * BUCKET_TICKS + 10);
+    assert_eq!(stats.completed_buckets(), 2);
+    assert_eq!(stats.history().collect::<Vec<_>>(), vec![1, 1]);
+    assert_eq!(counts(&stats, 10, &Item::A).get(ItemEventKind::Produced), 2);
+}
+
+#[test]
+fn per_minute_scales_by_window_length() {
+    let mut stats = ThroughputStats::default();
+    fill_bucket(
+        &mut stats,
+        0,
+        &[
+            event(ItemEventKind::Delivered, Item::A, None),
+            event(ItemEventKind::Delivered, Item::A, None),
+        ],
+    );
+    let per_minute = counts(&stats, 1, &Item::A).per_minute(stats.window_seconds(1));
+    assert_eq!(per_minute[ItemEventKind::Delivered as usize], 120.0);
+    assert_eq!(per_minute[ItemEventKind::Produced as usize], 0.0);
+}